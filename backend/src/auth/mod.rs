@@ -0,0 +1,448 @@
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::db::{decrement_api_key_usage, get_api_key_by_hash, get_api_key_usage, increment_api_key_usage};
+use crate::models::ApiResponse;
+use crate::state::AppState;
+
+mod jwt;
+
+pub use jwt::JwtVerifier;
+
+// How long a looked-up key is trusted before Cassandra is asked again (bounds revocation delay)
+const KEY_CACHE_TTL: Duration = Duration::from_secs(60);
+
+// Length of the rate limit window
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+// Prefix of issued keys, so they are recognizable in logs and secret scanners
+const KEY_PREFIX: &str = "crk_";
+
+/// An issued API key. Only the SHA-256 of the key is stored; the key itself is shown once.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    /// First characters of the key, to tell keys apart without revealing them
+    pub key_prefix: String,
+    pub rate_limit_per_minute: u32,
+    /// Monthly request quota for `/crawl`; `None` means unlimited
+    pub crawl_monthly_quota: Option<i64>,
+    /// Monthly request quota for `/social/*`; `None` means unlimited
+    pub social_monthly_quota: Option<i64>,
+    pub revoked: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiKeyRequest {
+    pub name: String,
+    pub rate_limit_per_minute: Option<u32>,
+    pub crawl_monthly_quota: Option<i64>,
+    pub social_monthly_quota: Option<i64>,
+}
+
+/// Returned once when a key is issued
+#[derive(Debug, Serialize)]
+pub struct IssuedApiKey {
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: ApiKey,
+}
+
+/// Which metered resource a request spends quota on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaScope {
+    Crawl,
+    Social,
+}
+
+impl QuotaScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuotaScope::Crawl => "crawl",
+            QuotaScope::Social => "social",
+        }
+    }
+
+    fn for_path(path: &str) -> Option<Self> {
+        if path == "/crawl" {
+            Some(QuotaScope::Crawl)
+        } else if path.starts_with("/social/") {
+            Some(QuotaScope::Social)
+        } else {
+            None
+        }
+    }
+}
+
+impl ApiKey {
+    fn monthly_quota(&self, scope: QuotaScope) -> Option<i64> {
+        match scope {
+            QuotaScope::Crawl => self.crawl_monthly_quota,
+            QuotaScope::Social => self.social_monthly_quota,
+        }
+    }
+}
+
+/// SHA-256 of a key, hex encoded; this is what Cassandra stores
+pub fn hash_api_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Generate a new random key
+pub fn generate_api_key() -> String {
+    format!("{}{}{}", KEY_PREFIX, Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Usage bucket for quotas, e.g. `2024-05`
+pub fn current_month() -> String {
+    Utc::now().format("%Y-%m").to_string()
+}
+
+struct RateWindow {
+    started: Instant,
+    count: u32,
+}
+
+/// Key lookups cached in memory and per-key rate limit windows. Rate limits are enforced per
+/// replica; monthly quotas are counted in Cassandra and shared by all replicas.
+pub struct ApiKeyStore {
+    enabled: bool,
+    admin_key_hash: Option<String>,
+    default_rate_limit: u32,
+    cache: Mutex<HashMap<String, (ApiKey, Instant)>>,
+    windows: Mutex<HashMap<Uuid, RateWindow>>,
+}
+
+impl ApiKeyStore {
+    pub fn from_env() -> Self {
+        let enabled = std::env::var("AUTH_ENABLED")
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(true);
+        let admin_key_hash = std::env::var("ADMIN_API_KEY")
+            .ok()
+            .filter(|key| !key.trim().is_empty())
+            .map(|key| hash_api_key(key.trim()));
+        let default_rate_limit = std::env::var("API_KEY_DEFAULT_RATE_LIMIT")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(60);
+
+        Self {
+            enabled,
+            admin_key_hash,
+            default_rate_limit,
+            cache: Mutex::new(HashMap::new()),
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn has_admin_key(&self) -> bool {
+        self.admin_key_hash.is_some()
+    }
+
+    pub fn default_rate_limit(&self) -> u32 {
+        self.default_rate_limit
+    }
+
+    /// Drop a key from the cache so a revocation takes effect on this replica immediately
+    pub fn forget(&self, key_id: Uuid) {
+        self.cache.lock().unwrap().retain(|_, (key, _)| key.id != key_id);
+        self.windows.lock().unwrap().remove(&key_id);
+    }
+
+    fn cached(&self, key_hash: &str) -> Option<ApiKey> {
+        let cache = self.cache.lock().unwrap();
+        cache
+            .get(key_hash)
+            .filter(|(_, fetched_at)| fetched_at.elapsed() < KEY_CACHE_TTL)
+            .map(|(key, _)| key.clone())
+    }
+
+    async fn lookup(&self, state: &AppState, key_hash: &str) -> Result<Option<ApiKey>, String> {
+        if let Some(key) = self.cached(key_hash) {
+            return Ok(Some(key));
+        }
+        let cassandra = &state.cassandra;
+        let key = get_api_key_by_hash(&cassandra.session, &cassandra.keyspace, key_hash)
+            .await
+            .map_err(|e| e.to_string())?;
+        if let Some(key) = &key {
+            self.cache
+                .lock()
+                .unwrap()
+                .insert(key_hash.to_string(), (key.clone(), Instant::now()));
+        }
+        Ok(key)
+    }
+
+    // Count one request against the key's per-minute window; on rejection returns the wait
+    fn check_rate(&self, key: &ApiKey) -> Result<(), Duration> {
+        let mut windows = self.windows.lock().unwrap();
+        let window = windows.entry(key.id).or_insert_with(|| RateWindow {
+            started: Instant::now(),
+            count: 0,
+        });
+        if window.started.elapsed() >= RATE_WINDOW {
+            window.started = Instant::now();
+            window.count = 0;
+        }
+        if window.count >= key.rate_limit_per_minute {
+            return Err(RATE_WINDOW.saturating_sub(window.started.elapsed()));
+        }
+        window.count += 1;
+        Ok(())
+    }
+
+    fn is_admin(&self, token: &str) -> bool {
+        self.admin_key_hash.as_deref() == Some(hash_api_key(token).as_str())
+    }
+}
+
+// Helper function to read the token from `Authorization: Bearer <token>`
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
+
+fn unauthorized(message: &str) -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, "Bearer")],
+        Json(ApiResponse::<()>::error(message)),
+    )
+        .into_response()
+}
+
+fn too_many_requests(message: &str, retry_after: Duration) -> Response {
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, retry_after.as_secs().max(1).to_string())],
+        Json(ApiResponse::<()>::error(message)),
+    )
+        .into_response()
+}
+
+fn database_error(e: impl std::fmt::Display) -> Response {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ApiResponse::<()>::error(&format!("Database error: {}", e))),
+    )
+        .into_response()
+}
+
+// Helper function to spend one unit of the key's monthly quota, if the scope is metered.
+// The request is counted first and the total compared afterwards, so concurrent requests
+// (on any replica) cannot all pass a check made before any of them was counted.
+async fn check_quota(state: &AppState, key: &ApiKey, scope: QuotaScope) -> Result<(), Response> {
+    let cassandra = &state.cassandra;
+    let month = current_month();
+
+    let Some(quota) = key.monthly_quota(scope) else {
+        if let Err(e) = increment_api_key_usage(&cassandra.session, &cassandra.keyspace, key.id, &month, scope.as_str()).await {
+            eprintln!("[Cassandra] Failed to record usage for API key {}: {}", key.id, e);
+        }
+        return Ok(());
+    };
+
+    increment_api_key_usage(&cassandra.session, &cassandra.keyspace, key.id, &month, scope.as_str())
+        .await
+        .map_err(database_error)?;
+    let used = get_api_key_usage(&cassandra.session, &cassandra.keyspace, key.id, &month)
+        .await
+        .map_err(database_error)?
+        .into_iter()
+        .find(|(usage_scope, _)| usage_scope == scope.as_str())
+        .map(|(_, count)| count)
+        .unwrap_or(0);
+    if used <= quota {
+        return Ok(());
+    }
+
+    // Rejected requests do not count; give the unit back so usage reports what was served
+    if let Err(e) = decrement_api_key_usage(&cassandra.session, &cassandra.keyspace, key.id, &month, scope.as_str()).await {
+        eprintln!("[Cassandra] Failed to release usage for API key {}: {}", key.id, e);
+    }
+
+    // Quotas reset at the start of the next UTC month
+    let now = Utc::now();
+    let next_month = now
+        .date_naive()
+        .with_day(1)
+        .and_then(|first| first.checked_add_months(chrono::Months::new(1)))
+        .and_then(|first| first.and_hms_opt(0, 0, 0))
+        .map(|start| start.and_utc());
+    let retry_after = next_month
+        .and_then(|start| (start - now).to_std().ok())
+        .unwrap_or(RATE_WINDOW);
+    Err(too_many_requests(
+        &format!("Monthly {} quota of {} requests exhausted", scope.as_str(), quota),
+        retry_after,
+    ))
+}
+
+/// What a caller may do. Each role includes the ones before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Read stored results, schedules and webhooks
+    Viewer,
+    /// Also trigger crawls and social proxies, and manage schedules and webhooks
+    Crawler,
+    /// Also use `/admin/*`
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Crawler => "crawler",
+            Role::Admin => "admin",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "viewer" => Some(Role::Viewer),
+            "crawler" => Some(Role::Crawler),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+}
+
+/// Who is calling: a JWT user, an API key (always the crawler role) or the admin key
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub subject: String,
+    pub role: Role,
+    pub api_key: Option<ApiKey>,
+}
+
+/// Extractor for the authenticated caller. Accepts `Authorization: Bearer` with an issued API
+/// key, the `ADMIN_API_KEY`, or a JWT; rejects with 401 otherwise. With `AUTH_ENABLED=false`
+/// every caller is treated as an admin.
+pub struct Authenticated(pub Principal);
+
+impl FromRequestParts<AppState> for Authenticated {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        if let Some(principal) = parts.extensions.get::<Principal>() {
+            return Ok(Authenticated(principal.clone()));
+        }
+
+        let store = state.api_keys.clone();
+        if !store.enabled {
+            return Ok(Authenticated(Principal {
+                subject: "anonymous".to_string(),
+                role: Role::Admin,
+                api_key: None,
+            }));
+        }
+
+        let token = bearer_token(&parts.headers)
+            .ok_or_else(|| unauthorized("Missing credentials; send Authorization: Bearer <API key or token>"))?;
+
+        let principal = if store.is_admin(token) {
+            Principal {
+                subject: "admin".to_string(),
+                role: Role::Admin,
+                api_key: None,
+            }
+        } else if token.starts_with(KEY_PREFIX) {
+            match store.lookup(state, &hash_api_key(token)).await {
+                Ok(Some(key)) if !key.revoked => Principal {
+                    subject: format!("api-key:{}", key.id),
+                    role: Role::Crawler,
+                    api_key: Some(key),
+                },
+                Ok(_) => return Err(unauthorized("Invalid or revoked API key")),
+                Err(e) => return Err(database_error(e)),
+            }
+        } else if state.jwt.is_configured() {
+            let verified = state.jwt.verify(token, &state.http.trusted()).await.map_err(|e| unauthorized(&e))?;
+            Principal {
+                subject: verified.subject,
+                role: verified.role,
+                api_key: None,
+            }
+        } else {
+            return Err(unauthorized("Invalid API key"));
+        };
+
+        parts.extensions.insert(principal.clone());
+        Ok(Authenticated(principal))
+    }
+}
+
+fn forbidden(message: &str) -> Response {
+    (StatusCode::FORBIDDEN, Json(ApiResponse::<()>::error(message))).into_response()
+}
+
+/// Middleware for client routes. Reads (`GET`) need the viewer role; everything else, including
+/// `/crawl` and `/social/*`, needs the crawler role. API keys are rate limited and, on `/crawl` and
+/// `/social/*`, metered against their monthly quotas.
+pub async fn authorize(
+    State(state): State<AppState>,
+    Authenticated(principal): Authenticated,
+    request: Request,
+    next: Next,
+) -> Response {
+    let required = if request.method() == Method::GET || request.method() == Method::HEAD {
+        Role::Viewer
+    } else {
+        Role::Crawler
+    };
+    if principal.role < required {
+        return forbidden(&format!("This endpoint requires the {} role", required.as_str()));
+    }
+
+    if let Some(key) = &principal.api_key {
+        if let Err(retry_after) = state.api_keys.check_rate(key) {
+            return too_many_requests(
+                &format!("Rate limit of {} requests per minute exceeded", key.rate_limit_per_minute),
+                retry_after,
+            );
+        }
+        if let Some(scope) = QuotaScope::for_path(request.uri().path()) {
+            if let Err(resp) = check_quota(&state, key, scope).await {
+                return resp;
+            }
+        }
+    }
+
+    next.run(request).await
+}
+
+/// Middleware for `/admin/*`: requires the admin role (the `ADMIN_API_KEY` or an admin JWT)
+pub async fn require_admin(Authenticated(principal): Authenticated, request: Request, next: Next) -> Response {
+    if principal.role < Role::Admin {
+        return forbidden("This endpoint requires the admin role");
+    }
+    next.run(request).await
+}
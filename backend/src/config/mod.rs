@@ -1,4 +1,10 @@
+use serde::de::DeserializeOwned;
 use std::env;
+use thiserror::Error;
+
+mod social;
+
+pub use social::{CredentialEntry, SocialCredentials, SocialProxyAllowlist};
 
 pub struct Config {
     pub database_url: String,
@@ -20,4 +26,160 @@ impl Config {
             server_port,
         }
     }
-}
\ No newline at end of file
+}
+
+// Helper function to read a positive numeric limit from the environment
+fn env_limit<T: std::str::FromStr + PartialOrd + Default>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|v| v.parse::<T>().ok())
+        .filter(|v| *v > T::default())
+        .unwrap_or(default)
+}
+
+/// A security-relevant setting that is set but unusable. The server refuses to start with it
+/// rather than fall back to defaults the operator did not choose.
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("{name} is not valid JSON: {reason}")]
+    InvalidJson { name: String, reason: String },
+
+    #[error("{name} names a file that cannot be read ({path}): {reason}")]
+    UnreadableFile { name: String, path: String, reason: String },
+
+    #[error("HTTP_PROXY_URL is not a valid proxy URL: {0}")]
+    InvalidProxy(String),
+
+    #[error("The outbound HTTP client cannot be built: {0}")]
+    HttpClient(String),
+}
+
+/// Read JSON tuning configuration from the environment variable `name`. Invalid JSON is logged
+/// with the variable's name and treated as unset, so a bad value falls back to the defaults
+/// instead of stopping the server. Security-relevant settings use `env_json_or_file` instead.
+pub fn env_json<T: DeserializeOwned>(name: &str) -> Option<T> {
+    let raw = env::var(name).ok().filter(|v| !v.trim().is_empty())?;
+    match parse_json(name, &raw) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            eprintln!("[Config] Ignoring {}, using defaults", e);
+            None
+        }
+    }
+}
+
+/// Read security-relevant JSON configuration (allowlists, credentials) from the environment
+/// variable `name`, or from the file named by `{name}_FILE` when `name` is unset. `Ok(None)`
+/// means neither is set; an invalid value or unreadable file is an error.
+pub fn env_json_or_file<T: DeserializeOwned>(name: &str) -> Result<Option<T>, ConfigError> {
+    if let Some(raw) = env::var(name).ok().filter(|v| !v.trim().is_empty()) {
+        return parse_json(name, &raw).map(Some);
+    }
+
+    let file_var = format!("{}_FILE", name);
+    let path = match env::var(&file_var).ok().filter(|v| !v.trim().is_empty()) {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let raw = std::fs::read_to_string(&path).map_err(|e| ConfigError::UnreadableFile {
+        name: file_var.clone(),
+        path: path.clone(),
+        reason: e.to_string(),
+    })?;
+    parse_json(&file_var, &raw).map(Some)
+}
+
+// Helper function to parse a JSON setting, naming the variable it came from on failure
+fn parse_json<T: DeserializeOwned>(name: &str, raw: &str) -> Result<T, ConfigError> {
+    serde_json::from_str(raw).map_err(|e| ConfigError::InvalidJson {
+        name: name.to_string(),
+        reason: e.to_string(),
+    })
+}
+
+/// Server-side limits on what a single crawl request may ask for
+#[derive(Debug, Clone)]
+pub struct CrawlLimits {
+    pub max_urls_per_request: usize,
+    pub max_pages: usize,
+    pub max_depth: usize,
+    pub max_time_seconds: u64,
+    pub max_concurrent_crawls: usize,
+    pub max_queued_crawls: usize,
+    pub retry_after_seconds: u64,
+}
+
+impl CrawlLimits {
+    pub fn from_env() -> Self {
+        Self {
+            max_urls_per_request: env_limit("CRAWL_MAX_URLS_PER_REQUEST", 10),
+            max_pages: env_limit("CRAWL_MAX_PAGES", 500),
+            max_depth: env_limit("CRAWL_MAX_DEPTH", 10),
+            max_time_seconds: env_limit("CRAWL_MAX_TIME_SECONDS", 600),
+            max_concurrent_crawls: env_limit("CRAWL_MAX_CONCURRENT", 4),
+            max_queued_crawls: env_limit("CRAWL_MAX_QUEUED", 8),
+            retry_after_seconds: env_limit("CRAWL_RETRY_AFTER_SECONDS", 30),
+        }
+    }
+}
+
+/// Server-side caps on how far one social proxy call may follow pagination cursors
+#[derive(Debug, Clone)]
+pub struct SocialPagingLimits {
+    pub max_pages: usize,
+    pub max_items: usize,
+    pub time_budget_seconds: u64,
+}
+
+impl SocialPagingLimits {
+    pub fn from_env() -> Self {
+        Self {
+            max_pages: env_limit("SOCIAL_MAX_PAGES", 10),
+            max_items: env_limit("SOCIAL_MAX_ITEMS", 500),
+            time_budget_seconds: env_limit("SOCIAL_PAGINATION_BUDGET_SECONDS", 30),
+        }
+    }
+}
+
+/// How much of a raw pass-through body is kept in `social_results`
+#[derive(Debug, Clone)]
+pub struct SocialRawLimits {
+    /// Larger bodies are streamed but not stored; `0` stores none
+    pub persist_max_bytes: usize,
+}
+
+impl SocialRawLimits {
+    pub fn from_env() -> Self {
+        Self {
+            persist_max_bytes: env::var("SOCIAL_RAW_PERSIST_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(1024 * 1024),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn invalid_json_names_the_variable() {
+        let err = parse_json::<HashMap<String, u64>>("SOCIAL_PROXY_ALLOWLIST", "{\"rapidapi\": [").unwrap_err();
+        assert!(matches!(err, ConfigError::InvalidJson { ref name, .. } if name == "SOCIAL_PROXY_ALLOWLIST"));
+        assert!(err.to_string().starts_with("SOCIAL_PROXY_ALLOWLIST is not valid JSON"));
+    }
+
+    #[test]
+    fn valid_json_is_parsed() {
+        let ttls: HashMap<String, u64> = parse_json("SOCIAL_CACHE_TTLS", r#"{"tikhub": 60}"#).unwrap();
+        assert_eq!(ttls["tikhub"], 60);
+    }
+
+    #[test]
+    fn unset_security_settings_are_not_an_error() {
+        let value: Option<HashMap<String, u64>> = env_json_or_file("CONFIG_TEST_SETTING_THAT_IS_NEVER_SET").unwrap();
+        assert!(value.is_none());
+    }
+}
@@ -0,0 +1,153 @@
+use serde::Deserialize;
+
+use super::{env_json_or_file, ConfigError};
+
+/// One upstream target the generic social proxies may call
+#[derive(Debug, Clone, Deserialize)]
+pub struct AllowedTarget {
+    /// RapidAPI host (e.g. `twitter-v24.p.rapidapi.com`) or TikHub service (e.g. `twitter/web`)
+    pub target: String,
+    /// Allowed paths; `*` allows any path and a trailing `*` allows a prefix
+    #[serde(default = "default_paths")]
+    pub paths: Vec<String>,
+    #[serde(default = "default_methods")]
+    pub methods: Vec<String>,
+}
+
+fn default_paths() -> Vec<String> {
+    vec!["*".to_string()]
+}
+
+fn default_methods() -> Vec<String> {
+    vec!["GET".to_string()]
+}
+
+impl AllowedTarget {
+    fn new(target: &str, methods: &[&str]) -> Self {
+        Self {
+            target: target.to_string(),
+            paths: default_paths(),
+            methods: methods.iter().map(|m| m.to_string()).collect(),
+        }
+    }
+
+    fn allows(&self, path: &str, method: &str) -> bool {
+        let method_allowed = self.methods.iter().any(|m| m.eq_ignore_ascii_case(method));
+        let path_allowed = self.paths.iter().any(|pattern| {
+            let pattern = pattern.trim_start_matches('/');
+            match pattern.strip_suffix('*') {
+                Some(prefix) => path.starts_with(prefix),
+                None => path == pattern,
+            }
+        });
+        method_allowed && path_allowed
+    }
+}
+
+/// Registry of upstream targets reachable through the social proxy routes, including
+/// `/social/providers/{name}` and the generic `/social/rapidapi/generic` and `/social/tikhub/generic`. Read from the JSON in `SOCIAL_PROXY_ALLOWLIST`, or the file named by
+/// `SOCIAL_PROXY_ALLOWLIST_FILE`; defaults to the hosts and services of the dedicated endpoints.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SocialProxyAllowlist {
+    #[serde(default)]
+    pub rapidapi: Vec<AllowedTarget>,
+    #[serde(default)]
+    pub tikhub: Vec<AllowedTarget>,
+}
+
+impl Default for SocialProxyAllowlist {
+    fn default() -> Self {
+        Self {
+            rapidapi: vec![
+                AllowedTarget::new("instagram-scraper-api2.p.rapidapi.com", &["GET", "POST"]),
+                AllowedTarget::new("twitter-v24.p.rapidapi.com", &["GET", "POST"]),
+            ],
+            tikhub: vec![
+                AllowedTarget::new("twitter/web", &["GET", "POST"]),
+                AllowedTarget::new("tiktok/web", &["GET", "POST"]),
+            ],
+        }
+    }
+}
+
+impl SocialProxyAllowlist {
+    /// The configured allowlist, or the defaults when none is configured. Invalid JSON is an
+    /// error: falling back to the defaults could expose targets the operator meant to drop.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Ok(env_json_or_file("SOCIAL_PROXY_ALLOWLIST")?.unwrap_or_default())
+    }
+
+    /// Whether `method path` may be sent to this RapidAPI host
+    pub fn allows_rapidapi(&self, host: &str, path: &str, method: &str) -> bool {
+        Self::allows(&self.rapidapi, host, path, method)
+    }
+
+    /// Whether `method path` may be sent to this TikHub service
+    pub fn allows_tikhub(&self, service: &str, path: &str, method: &str) -> bool {
+        Self::allows(&self.tikhub, service, path, method)
+    }
+
+    fn allows(targets: &[AllowedTarget], target: &str, path: &str, method: &str) -> bool {
+        let target = target.trim_matches('/');
+        let path = path.trim_start_matches('/');
+        // Never let a path climb out of the allowed prefix
+        if path.split('/').any(|segment| segment == ".." || segment == ".")
+            || path.contains('\\')
+            || path.to_ascii_lowercase().contains("%2e")
+        {
+            return false;
+        }
+        targets
+            .iter()
+            .filter(|allowed| allowed.target.trim_matches('/').eq_ignore_ascii_case(target))
+            .any(|allowed| allowed.allows(path, method))
+    }
+}
+
+/// One upstream API key in a credential pool
+#[derive(Debug, Clone, Deserialize)]
+pub struct CredentialEntry {
+    pub key: String,
+    /// Shown to admins instead of the key; defaults to the key's last four characters
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// Upstream API keys per provider kind. Read from the JSON in `SOCIAL_CREDENTIALS`, or the file
+/// named by `SOCIAL_CREDENTIALS_FILE`; otherwise from `TIKHUB_TOKEN` and `RAPIDAPI_KEY`, which
+/// may each hold several comma-separated keys.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SocialCredentials {
+    #[serde(default)]
+    pub tikhub: Vec<CredentialEntry>,
+    #[serde(default)]
+    pub rapidapi: Vec<CredentialEntry>,
+}
+
+impl SocialCredentials {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        if let Some(credentials) = env_json_or_file("SOCIAL_CREDENTIALS")? {
+            return Ok(credentials);
+        }
+
+        let non_empty = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+        let keys = |name: &str| -> Vec<CredentialEntry> {
+            non_empty(name)
+                .map(|v| {
+                    v.split(',')
+                        .map(str::trim)
+                        .filter(|key| !key.is_empty())
+                        .map(|key| CredentialEntry {
+                            key: key.to_string(),
+                            label: None,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        Ok(Self {
+            tikhub: keys("TIKHUB_TOKEN"),
+            rapidapi: keys("RAPIDAPI_KEY"),
+        })
+    }
+}
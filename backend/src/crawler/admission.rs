@@ -0,0 +1,132 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Duration;
+
+/// Returned when the crawler pool and its queue are both full
+#[derive(Debug)]
+pub struct PoolSaturated {
+    pub retry_after: Duration,
+}
+
+struct QueueSlot<'a>(&'a AtomicUsize);
+
+impl Drop for QueueSlot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Bounded pool of concurrent crawls with a bounded wait queue in front of it
+pub struct CrawlAdmission {
+    permits: Arc<Semaphore>,
+    queued: AtomicUsize,
+    max_queued: usize,
+    retry_after: Duration,
+}
+
+impl CrawlAdmission {
+    pub fn new(max_concurrent: usize, max_queued: usize, retry_after: Duration) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(max_concurrent)),
+            queued: AtomicUsize::new(0),
+            max_queued,
+            retry_after,
+        }
+    }
+
+    /// Take a crawl slot, waiting in the queue if there is room, or fail fast when saturated
+    pub async fn admit(&self) -> Result<OwnedSemaphorePermit, PoolSaturated> {
+        if let Ok(permit) = self.permits.clone().try_acquire_owned() {
+            return Ok(permit);
+        }
+
+        let position = self.queued.fetch_add(1, Ordering::SeqCst);
+        if position >= self.max_queued {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            return Err(PoolSaturated {
+                retry_after: self.retry_after,
+            });
+        }
+
+        // Leave the queue even if the client disconnects while waiting
+        let _queued = QueueSlot(&self.queued);
+        Ok(self.admit_waiting().await)
+    }
+
+    /// Take a crawl slot, however long that takes (used by scheduled crawls)
+    pub async fn admit_waiting(&self) -> OwnedSemaphorePermit {
+        self.permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("crawl admission semaphore is never closed")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(max_concurrent: usize, max_queued: usize) -> CrawlAdmission {
+        CrawlAdmission::new(max_concurrent, max_queued, Duration::from_secs(7))
+    }
+
+    #[tokio::test]
+    async fn admits_immediately_while_slots_are_free() {
+        let admission = pool(2, 0);
+        let _first = admission.admit().await.unwrap();
+        let _second = admission.admit().await.unwrap();
+        assert!(admission.admit().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_with_retry_after_when_the_queue_is_full() {
+        let admission = Arc::new(pool(1, 1));
+        let permit = admission.admit().await.unwrap();
+
+        // One crawl waits in the queue, the next one is turned away
+        let waiting = tokio::spawn({
+            let admission = admission.clone();
+            async move { admission.admit().await.map(|_| ()) }
+        });
+        while admission.queued.load(Ordering::SeqCst) == 0 {
+            tokio::task::yield_now().await;
+        }
+        let saturated = admission.admit().await.unwrap_err();
+        assert_eq!(saturated.retry_after, Duration::from_secs(7));
+
+        // The queued crawl gets the slot once it is released
+        drop(permit);
+        assert!(waiting.await.unwrap().is_ok());
+        assert_eq!(admission.queued.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn cancelled_waiters_leave_the_queue() {
+        let admission = pool(1, 1);
+        let _permit = admission.admit().await.unwrap();
+
+        let cancelled = tokio::time::timeout(Duration::from_millis(20), admission.admit()).await;
+        assert!(cancelled.is_err());
+        assert_eq!(admission.queued.load(Ordering::SeqCst), 0);
+
+        // The freed queue place can be taken again
+        let again = tokio::time::timeout(Duration::from_millis(20), admission.admit()).await;
+        assert!(again.is_err(), "still waiting for the slot rather than rejected");
+    }
+
+    #[tokio::test]
+    async fn waiting_admission_ignores_the_queue_limit() {
+        let admission = Arc::new(pool(1, 0));
+        let permit = admission.admit().await.unwrap();
+        assert!(admission.admit().await.is_err());
+
+        let scheduled = tokio::spawn({
+            let admission = admission.clone();
+            async move { drop(admission.admit_waiting().await) }
+        });
+        drop(permit);
+        tokio::time::timeout(Duration::from_secs(1), scheduled).await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,195 @@
+use regex::Regex;
+use std::sync::OnceLock;
+use url::Url;
+
+/// Query parameters dropped by default; a trailing `*` matches any suffix
+pub const DEFAULT_TRACKING_PARAMS: &[&str] = &[
+    "utm_*",
+    "fbclid",
+    "gclid",
+    "dclid",
+    "msclkid",
+    "yclid",
+    "mc_cid",
+    "mc_eid",
+    "_ga",
+    "_gl",
+    "igshid",
+    "ref_src",
+];
+
+// Helper function to check a query parameter name against the tracking param patterns
+fn is_tracking_param(name: &str, tracking_params: &[String]) -> bool {
+    let name = name.to_lowercase();
+    tracking_params.iter().any(|pattern| {
+        let pattern = pattern.to_lowercase();
+        match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == pattern,
+        }
+    })
+}
+
+/// Resolve the tracking params for a request, falling back to the defaults
+pub fn tracking_params_or_default(tracking_params: Option<&Vec<String>>) -> Vec<String> {
+    match tracking_params {
+        Some(params) => params.clone(),
+        None => DEFAULT_TRACKING_PARAMS.iter().map(|p| p.to_string()).collect(),
+    }
+}
+
+/// Canonicalize a URL: lowercase host, no default port, no fragment,
+/// sorted query parameters with tracking parameters removed.
+pub fn canonicalize_url(url: &Url, tracking_params: &[String]) -> Url {
+    let mut canonical = url.clone();
+
+    // `Url::parse` already lowercases hosts of http(s) URLs; this covers any other origin
+    if let Some(host) = canonical.host_str().map(|h| h.to_lowercase()) {
+        let _ = canonical.set_host(Some(&host));
+    }
+
+    if canonical.port().is_some() && canonical.port() == default_port(canonical.scheme()) {
+        let _ = canonical.set_port(None);
+    }
+
+    canonical.set_fragment(None);
+
+    let mut params: Vec<(String, String)> = canonical
+        .query_pairs()
+        .filter(|(name, _)| !is_tracking_param(name, tracking_params))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    params.sort();
+
+    if params.is_empty() {
+        canonical.set_query(None);
+    } else {
+        canonical.query_pairs_mut().clear().extend_pairs(params);
+    }
+
+    if canonical.path().is_empty() {
+        canonical.set_path("/");
+    }
+
+    canonical
+}
+
+// Helper function to get the default port of the schemes we crawl
+fn default_port(scheme: &str) -> Option<u16> {
+    match scheme {
+        "http" => Some(80),
+        "https" => Some(443),
+        _ => None,
+    }
+}
+
+/// Extract `<link rel="canonical" href="...">` from a page, resolved against the page URL
+pub fn extract_canonical_link(html: &str, page_url: &Url) -> Option<Url> {
+    static LINK_TAG: OnceLock<Regex> = OnceLock::new();
+    static HREF: OnceLock<Regex> = OnceLock::new();
+
+    let link_regex =
+        LINK_TAG.get_or_init(|| Regex::new(r#"(?i)<link[^>]*\brel\s*=\s*["']?canonical["']?[^>]*>"#).unwrap());
+    let href_regex = HREF.get_or_init(|| Regex::new(r#"(?i)\bhref\s*=\s*["']([^"']+)["']"#).unwrap());
+
+    let tag = link_regex.find(html)?;
+    let href = href_regex.captures(tag.as_str())?.get(1)?.as_str().trim();
+
+    page_url
+        .join(href)
+        .ok()
+        .filter(|url| url.scheme() == "http" || url.scheme() == "https")
+}
+
+/// Key used to deduplicate a crawled page: its canonical link when present, else its own URL.
+/// Canonical links to another host, or from a deeper page to the seed or the site root, are
+/// ignored: a template that points every page at the homepage would otherwise collapse the
+/// whole crawl into one page.
+pub fn page_dedup_key(html: &str, page_url: &str, seed_url: &Url, tracking_params: &[String]) -> Option<Url> {
+    let page_url = canonicalize_url(&Url::parse(page_url).ok()?, tracking_params);
+    let Some(canonical) = extract_canonical_link(html, &page_url).map(|url| canonicalize_url(&url, tracking_params)) else {
+        return Some(page_url);
+    };
+
+    let same_host = canonical.host_str() == page_url.host_str();
+    let mut site_root = seed_url.clone();
+    site_root.set_path("/");
+    site_root.set_query(None);
+    let points_at_seed = [canonicalize_url(seed_url, tracking_params), canonicalize_url(&site_root, tracking_params)]
+        .iter()
+        .any(|seed| *seed == canonical && *seed != page_url);
+
+    if same_host && !points_at_seed {
+        Some(canonical)
+    } else {
+        Some(page_url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> Vec<String> {
+        tracking_params_or_default(None)
+    }
+
+    fn dedup_key(html: &str, page_url: &str) -> String {
+        let seed = Url::parse("https://news.example.com/world/").unwrap();
+        page_dedup_key(html, page_url, &seed, &defaults()).unwrap().to_string()
+    }
+
+    #[test]
+    fn canonicalize_drops_tracking_params_fragments_and_default_ports() {
+        let url = Url::parse("https://News.Example.com:443/a?utm_source=x&b=2&a=1&fbclid=y#top").unwrap();
+
+        assert_eq!(canonicalize_url(&url, &defaults()).as_str(), "https://news.example.com/a?a=1&b=2");
+    }
+
+    #[test]
+    fn custom_tracking_params_replace_the_defaults() {
+        let url = Url::parse("https://example.com/?utm_source=x&session=1").unwrap();
+        let params = tracking_params_or_default(Some(&vec!["session".to_string()]));
+
+        assert_eq!(canonicalize_url(&url, &params).as_str(), "https://example.com/?utm_source=x");
+    }
+
+    #[test]
+    fn canonical_link_is_resolved_against_the_page() {
+        let page = Url::parse("https://example.com/news/story?page=2").unwrap();
+        let html = r#"<head><LINK href='/news/story' REL="canonical"></head>"#;
+
+        assert_eq!(extract_canonical_link(html, &page).unwrap().as_str(), "https://example.com/news/story");
+        assert_eq!(extract_canonical_link("<link rel=\"canonical\" href=\"ftp://example.com/x\">", &page), None);
+        assert_eq!(extract_canonical_link("<link rel=\"alternate\" href=\"/x\">", &page), None);
+    }
+
+    #[test]
+    fn dedup_key_follows_a_same_host_canonical() {
+        let html = r#"<link rel="canonical" href="https://news.example.com/world/story-1">"#;
+
+        assert_eq!(
+            dedup_key(html, "https://news.example.com/world/story-1?utm_medium=rss&amp=1"),
+            "https://news.example.com/world/story-1"
+        );
+        assert_eq!(dedup_key("<p>no canonical</p>", "https://news.example.com/world/story-2#c"), "https://news.example.com/world/story-2");
+    }
+
+    #[test]
+    fn dedup_key_ignores_canonicals_to_other_hosts() {
+        let html = r#"<link rel="canonical" href="https://syndication.example.org/story-1">"#;
+
+        assert_eq!(dedup_key(html, "https://news.example.com/world/story-1"), "https://news.example.com/world/story-1");
+    }
+
+    #[test]
+    fn dedup_key_ignores_canonicals_to_the_seed_or_site_root() {
+        let to_root = r#"<link rel="canonical" href="/">"#;
+        let to_seed = r#"<link rel="canonical" href="https://news.example.com/world/">"#;
+
+        assert_eq!(dedup_key(to_root, "https://news.example.com/world/story-1"), "https://news.example.com/world/story-1");
+        assert_eq!(dedup_key(to_seed, "https://news.example.com/world/story-2"), "https://news.example.com/world/story-2");
+        // A variant of the seed itself still collapses into it
+        assert_eq!(dedup_key(to_seed, "https://news.example.com/world/?utm_source=x"), "https://news.example.com/world/");
+    }
+}
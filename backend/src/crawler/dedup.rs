@@ -0,0 +1,159 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+// Number of consecutive words hashed together as one shingle
+const SHINGLE_SIZE: usize = 3;
+
+/// Default similarity above which two pages are treated as the same document
+pub const DEFAULT_SIMILARITY_THRESHOLD: f32 = 0.9;
+
+// Helper function to hash a single shingle into 64 bits
+fn hash_shingle(words: &[String]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    words.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Compute a 64-bit SimHash fingerprint of cleaned page text.
+/// Returns `None` when the text is too short to fingerprint meaningfully.
+pub fn simhash(text: &str) -> Option<u64> {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .collect::<String>()
+                .to_lowercase()
+        })
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    if words.len() < SHINGLE_SIZE {
+        return None;
+    }
+
+    let mut weights = [0i64; 64];
+    for shingle in words.windows(SHINGLE_SIZE) {
+        let hash = hash_shingle(shingle);
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash & (1 << bit) != 0 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+
+    let fingerprint = weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0u64, |acc, (bit, _)| acc | (1 << bit));
+
+    Some(fingerprint)
+}
+
+/// Similarity of two fingerprints in the range 0.0 (unrelated) to 1.0 (identical)
+pub fn similarity(a: u64, b: u64) -> f32 {
+    1.0 - (a ^ b).count_ones() as f32 / 64.0
+}
+
+/// Tracks fingerprints of the pages kept so far in a crawl
+pub struct DuplicateDetector {
+    threshold: f32,
+    fingerprints: Vec<(u64, usize)>,
+}
+
+impl DuplicateDetector {
+    pub fn new(threshold: Option<f32>) -> Self {
+        Self {
+            threshold: threshold
+                .unwrap_or(DEFAULT_SIMILARITY_THRESHOLD)
+                .clamp(0.0, 1.0),
+            fingerprints: Vec::new(),
+        }
+    }
+
+    /// Return the index of the kept page this fingerprint duplicates, if any
+    pub fn find_duplicate(&self, fingerprint: u64) -> Option<usize> {
+        self.fingerprints
+            .iter()
+            .find(|(kept, _)| similarity(*kept, fingerprint) >= self.threshold)
+            .map(|(_, index)| *index)
+    }
+
+    /// Remember the fingerprint of a page kept at `index` in the page results
+    pub fn insert(&mut self, fingerprint: u64, index: usize) {
+        self.fingerprints.push((fingerprint, index));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARTICLE: &str = "The city council met on Tuesday evening to discuss the budget for the coming year. \
+        Members agreed to increase spending on public transport and road repairs, while funding for the \
+        library service will stay at the current level. The mayor said the plan balances the needs of \
+        residents with the pressure on local taxes, and a final vote is expected at the next meeting \
+        after a period of public consultation that runs until the end of the month.";
+
+    const UNRELATED: &str = "Heavy rain is forecast across the north of the country this weekend, with \
+        flood warnings issued for several rivers. Forecasters expect drier weather to return by Tuesday, \
+        although temperatures will remain below the seasonal average for most of next week and frost is \
+        possible overnight in rural areas away from the coast.";
+
+    #[test]
+    fn text_shorter_than_a_shingle_has_no_fingerprint() {
+        assert_eq!(simhash("two words"), None);
+        assert_eq!(simhash("  ... !!! "), None);
+        assert!(simhash("three whole words").is_some());
+    }
+
+    #[test]
+    fn fingerprints_ignore_case_and_punctuation() {
+        let shouted = ARTICLE.to_uppercase().replace('.', "!");
+
+        assert_eq!(simhash(ARTICLE), simhash(&shouted));
+        assert_eq!(similarity(simhash(ARTICLE).unwrap(), simhash(&shouted).unwrap()), 1.0);
+    }
+
+    #[test]
+    fn near_duplicates_are_closer_than_unrelated_pages() {
+        let article = simhash(ARTICLE).unwrap();
+        let edited = simhash(&ARTICLE.replace("Tuesday evening", "Tuesday night")).unwrap();
+        let unrelated = simhash(UNRELATED).unwrap();
+
+        assert!(similarity(article, edited) >= DEFAULT_SIMILARITY_THRESHOLD);
+        assert!(similarity(article, unrelated) < DEFAULT_SIMILARITY_THRESHOLD);
+    }
+
+    #[test]
+    fn similarity_counts_differing_bits() {
+        assert_eq!(similarity(0, 0), 1.0);
+        assert_eq!(similarity(0, u64::MAX), 0.0);
+        assert_eq!(similarity(0, 0xFFFF_FFFF), 0.5);
+    }
+
+    #[test]
+    fn detector_collapses_duplicates_onto_the_first_kept_page() {
+        let mut detector = DuplicateDetector::new(None);
+        let article = simhash(ARTICLE).unwrap();
+        detector.insert(article, 0);
+        detector.insert(simhash(UNRELATED).unwrap(), 1);
+
+        let edited = simhash(&ARTICLE.replace("Tuesday evening", "Tuesday night")).unwrap();
+        assert_eq!(detector.find_duplicate(edited), Some(0));
+        assert_eq!(detector.find_duplicate(!article), None);
+    }
+
+    #[test]
+    fn detector_threshold_is_clamped() {
+        // A threshold above 1.0 still treats identical pages as duplicates
+        let mut detector = DuplicateDetector::new(Some(1.5));
+        detector.insert(42, 3);
+
+        assert_eq!(detector.find_duplicate(42), Some(3));
+        assert_eq!(detector.find_duplicate(43), None);
+    }
+}
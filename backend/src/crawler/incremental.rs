@@ -0,0 +1,104 @@
+use reqwest::{header, Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use url::Url;
+
+use super::politeness::politeness;
+
+// Upper bound on conditional requests sent per domain before a recrawl
+const MAX_CONDITIONAL_CHECKS: usize = 200;
+
+/// Links stored per page for discovery when the page is not refetched
+pub const MAX_STORED_LINKS: usize = 500;
+
+// Timeout for a single conditional request
+const CONDITIONAL_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What we remember about a URL between crawls
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchState {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub content_hash: String,
+    /// Same-site links found on the page, used for link discovery when it is not refetched
+    #[serde(default)]
+    pub links: Vec<String>,
+}
+
+impl FetchState {
+    pub fn host(&self) -> Option<String> {
+        Url::parse(&self.url).ok()?.host_str().map(|h| h.to_string())
+    }
+}
+
+/// Fetch states from previous crawls, keyed by canonical URL
+pub type PreviousFetchStates = HashMap<String, FetchState>;
+
+/// SHA-256 of the cleaned page text, hex encoded
+pub fn content_hash(cleaned_text: &str) -> String {
+    hex::encode(Sha256::digest(cleaned_text.as_bytes()))
+}
+
+/// Send conditional GETs (If-None-Match / If-Modified-Since) for previously seen URLs of this
+/// domain and return the fetch states of those the server reports as 304 Not Modified, with
+/// the validators the 304 carried. These pages are not fetched again by the crawl. The seed is
+/// never skipped because it drives link discovery.
+pub async fn revalidate_unchanged(client: &Client, previous: &PreviousFetchStates, base_url: &Url) -> Vec<FetchState> {
+    let base_host = base_url.host_str().unwrap_or("");
+
+    let mut checks = JoinSet::new();
+    for state in previous
+        .values()
+        .filter(|state| state.etag.is_some() || state.last_modified.is_some())
+        .filter(|state| state.host().as_deref() == Some(base_host))
+        .filter(|state| state.url.trim_end_matches('/') != base_url.as_str().trim_end_matches('/'))
+        .take(MAX_CONDITIONAL_CHECKS)
+    {
+        let client = client.clone();
+        let state = state.clone();
+        let host = base_host.to_string();
+        checks.spawn(async move {
+            politeness().wait_for_token(&host).await;
+            let mut request = client.get(&state.url).timeout(CONDITIONAL_REQUEST_TIMEOUT);
+            if let Some(etag) = &state.etag {
+                request = request.header(header::IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &state.last_modified {
+                request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+            }
+            match request.send().await {
+                Ok(resp) if resp.status() == StatusCode::NOT_MODIFIED => Some(refreshed(state, resp.headers())),
+                _ => None,
+            }
+        });
+    }
+
+    let mut unchanged = Vec::new();
+    while let Some(result) = checks.join_next().await {
+        if let Ok(Some(state)) = result {
+            unchanged.push(state);
+        }
+    }
+    unchanged
+}
+
+// Helper function to take the validators a 304 response sent, keeping the stored ones it omits
+fn refreshed(mut state: FetchState, headers: &header::HeaderMap) -> FetchState {
+    let header_value = |name: header::HeaderName| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+    };
+    if let Some(etag) = header_value(header::ETAG) {
+        state.etag = Some(etag);
+    }
+    if let Some(last_modified) = header_value(header::LAST_MODIFIED) {
+        state.last_modified = Some(last_modified);
+    }
+    state
+}
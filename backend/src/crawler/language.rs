@@ -0,0 +1,215 @@
+use regex::Regex;
+use std::sync::OnceLock;
+use whatlang::Lang;
+
+// Minimum amount of text before we trust n-gram detection at all
+const MIN_DETECTION_CHARS: usize = 40;
+
+// Helper function to reduce a language tag such as "en-US" or "id_ID" to its primary subtag
+fn primary_subtag(tag: &str) -> Option<String> {
+    let primary = tag
+        .split(['-', '_', ',', ';'])
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_lowercase();
+
+    if primary.len() >= 2 && primary.len() <= 3 && primary.chars().all(|c| c.is_ascii_alphabetic()) {
+        Some(primary)
+    } else {
+        None
+    }
+}
+
+// Helper function to read the declared language from `<html lang>` or a Content-Language meta tag
+fn declared_html_language(html: &str) -> Option<String> {
+    static HTML_LANG: OnceLock<Regex> = OnceLock::new();
+    static META_LANGUAGE: OnceLock<Regex> = OnceLock::new();
+
+    let html_lang_regex =
+        HTML_LANG.get_or_init(|| Regex::new(r#"(?i)<html[^>]*\slang\s*=\s*["']?([A-Za-z_-]+)"#).unwrap());
+    if let Some(lang) = html_lang_regex
+        .captures(html)
+        .and_then(|cap| cap.get(1))
+        .and_then(|m| primary_subtag(m.as_str()))
+    {
+        return Some(lang);
+    }
+
+    let meta_regex = META_LANGUAGE.get_or_init(|| {
+        Regex::new(r#"(?i)<meta[^>]*http-equiv\s*=\s*["']content-language["'][^>]*content\s*=\s*["']([^"']+)["']"#)
+            .unwrap()
+    });
+    meta_regex
+        .captures(html)
+        .and_then(|cap| cap.get(1))
+        .and_then(|m| primary_subtag(m.as_str()))
+}
+
+// Map whatlang's ISO 639-3 languages onto the ISO 639-1 codes used by `<html lang>`
+fn iso_639_1(lang: Lang) -> &'static str {
+    match lang {
+        Lang::Afr => "af",
+        Lang::Aka => "ak",
+        Lang::Amh => "am",
+        Lang::Ara => "ar",
+        Lang::Aze => "az",
+        Lang::Bel => "be",
+        Lang::Ben => "bn",
+        Lang::Bul => "bg",
+        Lang::Cat => "ca",
+        Lang::Ces => "cs",
+        Lang::Cmn => "zh",
+        Lang::Dan => "da",
+        Lang::Deu => "de",
+        Lang::Ell => "el",
+        Lang::Eng => "en",
+        Lang::Epo => "eo",
+        Lang::Est => "et",
+        Lang::Fin => "fi",
+        Lang::Fra => "fr",
+        Lang::Guj => "gu",
+        Lang::Heb => "he",
+        Lang::Hin => "hi",
+        Lang::Hrv => "hr",
+        Lang::Hun => "hu",
+        Lang::Hye => "hy",
+        Lang::Ind => "id",
+        Lang::Ita => "it",
+        Lang::Jav => "jv",
+        Lang::Jpn => "ja",
+        Lang::Kan => "kn",
+        Lang::Kat => "ka",
+        Lang::Khm => "km",
+        Lang::Kor => "ko",
+        Lang::Lat => "la",
+        Lang::Lav => "lv",
+        Lang::Lit => "lt",
+        Lang::Mal => "ml",
+        Lang::Mar => "mr",
+        Lang::Mkd => "mk",
+        Lang::Mya => "my",
+        Lang::Nep => "ne",
+        Lang::Nld => "nl",
+        Lang::Nob => "nb",
+        Lang::Ori => "or",
+        Lang::Pan => "pa",
+        Lang::Pes => "fa",
+        Lang::Pol => "pl",
+        Lang::Por => "pt",
+        Lang::Ron => "ro",
+        Lang::Rus => "ru",
+        Lang::Sin => "si",
+        Lang::Slk => "sk",
+        Lang::Slv => "sl",
+        Lang::Sna => "sn",
+        Lang::Spa => "es",
+        Lang::Srp => "sr",
+        Lang::Swe => "sv",
+        Lang::Tam => "ta",
+        Lang::Tel => "te",
+        Lang::Tgl => "tl",
+        Lang::Tha => "th",
+        Lang::Tuk => "tk",
+        Lang::Tur => "tr",
+        Lang::Ukr => "uk",
+        Lang::Urd => "ur",
+        Lang::Uzb => "uz",
+        Lang::Vie => "vi",
+        Lang::Yid => "yi",
+        Lang::Zul => "zu",
+    }
+}
+
+// Helper function to detect the language of cleaned page text using trigram detection
+fn detect_text_language(text: &str) -> Option<String> {
+    if text.chars().count() < MIN_DETECTION_CHARS {
+        return None;
+    }
+
+    whatlang::detect(text)
+        .filter(|info| info.is_reliable())
+        .map(|info| iso_639_1(info.lang()).to_string())
+}
+
+/// Detect a page's language as an ISO 639-1 code.
+///
+/// The declared `<html lang>` (or Content-Language meta tag) wins, then the
+/// `Content-Language` response header, then n-gram detection on the cleaned text.
+pub fn detect_page_language(html: &str, content_language: Option<&str>, cleaned_text: &str) -> Option<String> {
+    declared_html_language(html)
+        .or_else(|| content_language.and_then(primary_subtag))
+        .or_else(|| detect_text_language(cleaned_text))
+}
+
+/// Check a detected language against the requested `languages` filter.
+/// Pages whose language could not be determined never match a non-empty filter.
+pub fn matches_language_filter(language: Option<&str>, languages: Option<&[String]>) -> bool {
+    let languages = match languages {
+        Some(languages) if !languages.is_empty() => languages,
+        _ => return true,
+    };
+
+    match language {
+        Some(lang) => languages
+            .iter()
+            .filter_map(|wanted| primary_subtag(wanted))
+            .any(|wanted| wanted == lang),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENGLISH: &str = "The city council announced today that the new bridge across the river should open next summer and that the construction work has so far gone ahead without any major delays.";
+    const GERMAN: &str = "Die Stadtverwaltung hat heute bekannt gegeben, dass die neue Brücke über den Fluss im nächsten Sommer eröffnet werden soll und dass die Bauarbeiten bisher ohne größere Verzögerungen verlaufen sind.";
+
+    #[test]
+    fn declared_html_language_wins_over_header_and_text() {
+        let html = r#"<!doctype html><html class="no-js" lang="de-AT"><body></body></html>"#;
+
+        assert_eq!(detect_page_language(html, Some("fr"), ENGLISH).as_deref(), Some("de"));
+    }
+
+    #[test]
+    fn content_language_meta_tag_is_read() {
+        let html = r#"<html><head><meta http-equiv="Content-Language" content="id_ID"></head></html>"#;
+
+        assert_eq!(detect_page_language(html, None, ENGLISH).as_deref(), Some("id"));
+    }
+
+    #[test]
+    fn response_header_is_used_when_the_page_declares_nothing() {
+        assert_eq!(detect_page_language("<html><body></body></html>", Some("fr-CA, en;q=0.5"), ENGLISH).as_deref(), Some("fr"));
+    }
+
+    #[test]
+    fn text_is_detected_only_above_the_minimum_length() {
+        assert_eq!(detect_page_language("<html>", None, ENGLISH).as_deref(), Some("en"));
+        assert_eq!(detect_page_language("<html>", None, GERMAN).as_deref(), Some("de"));
+
+        let short = &ENGLISH[..MIN_DETECTION_CHARS - 1];
+        assert_eq!(detect_page_language("<html>", None, short), None);
+    }
+
+    #[test]
+    fn malformed_declared_tags_are_ignored() {
+        assert_eq!(primary_subtag("english"), None);
+        assert_eq!(primary_subtag("x"), None);
+        assert_eq!(primary_subtag("EN-us").as_deref(), Some("en"));
+    }
+
+    #[test]
+    fn language_filter_matches_primary_subtags() {
+        let wanted = vec!["en-GB".to_string(), "de".to_string()];
+
+        assert!(matches_language_filter(Some("en"), Some(&wanted)));
+        assert!(!matches_language_filter(Some("fr"), Some(&wanted)));
+        // Unknown languages never match a filter, and no filter matches everything
+        assert!(!matches_language_filter(None, Some(&wanted)));
+        assert!(matches_language_filter(None, None));
+        assert!(matches_language_filter(Some("fr"), Some(&[])));
+    }
+}
@@ -2,13 +2,34 @@ use spider::website::Website;
 use spider::page::Page;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::time::timeout_at;
 use thiserror::Error;
 use url::Url;
 use regex::Regex;
 use chrono::{DateTime, NaiveDate};
 use html2text;
 
+mod admission;
+mod canonical;
+mod dedup;
+mod incremental;
+mod language;
+mod pagination;
+mod politeness;
+mod scope;
+use canonical::{canonicalize_url, page_dedup_key, tracking_params_or_default};
+use dedup::{simhash, DuplicateDetector};
+use incremental::{content_hash, revalidate_unchanged, MAX_STORED_LINKS};
+pub use incremental::{FetchState, PreviousFetchStates};
+use language::{detect_page_language, matches_language_filter};
+use pagination::{find_next_page, DEFAULT_MAX_PAGINATION_PAGES};
+use scope::{extract_links, CrawlScope, ExcludedLinkCount};
+use crate::outbound::{HostCheckCache, HttpClients, OutboundPolicy};
+pub use admission::{CrawlAdmission, PoolSaturated};
+pub use politeness::politeness;
+
 // Helper function to parse date string to NaiveDate
 fn parse_date_string(date_str: &str) -> Result<NaiveDate, CrawlerError> {
     NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
@@ -206,6 +227,12 @@ pub enum CrawlerError {
     #[error("Spider error: {0}")]
     SpiderError(String),
 
+    #[error("Pattern error: {0}")]
+    PatternError(String),
+
+    #[error("Blocked URL: {0}")]
+    BlockedUrl(String),
+
     #[error("Other error: {0}")]
     Other(String),
 }
@@ -219,6 +246,9 @@ pub struct CrawlResult {
     pub total_pages_crawled: usize,
     pub total_processing_time_ms: u64,
     pub crawl_timestamp: String,
+    /// The crawl hit its hard deadline; results cover only what was fetched before it
+    #[serde(default)]
+    pub truncated: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -229,8 +259,26 @@ pub struct DomainResult {
     pub matches: Vec<KeywordMatch>,
     pub pages_crawled: usize,
     pub has_more_pages: bool,
+    #[serde(default)]
+    pub truncated: bool,
+    #[serde(default)]
+    pub pages: Vec<PageResult>,
+    #[serde(default)]
+    pub excluded_links: Vec<ExcludedLinkCount>,
     pub metadata: Option<CrawlMetadata>,
     pub error: Option<String>,
+    #[serde(skip)]
+    pub fetch_states: Vec<FetchState>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PageResult {
+    pub url: String,
+    pub title: Option<String>,
+    pub language: Option<String>,
+    pub match_count: usize,
+    #[serde(default)]
+    pub duplicates: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -240,9 +288,15 @@ pub struct CrawlMetadata {
     pub content_summary: Option<String>,
     pub last_modified: Option<String>,
     pub published_date: Option<String>,
+    #[serde(default)]
+    pub duplicates_collapsed: usize,
+    #[serde(default)]
+    pub pagination_pages_followed: usize,
+    #[serde(default)]
+    pub unchanged_pages: usize,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeywordMatch {
     pub keyword: String,
     pub context: String,
@@ -252,21 +306,40 @@ pub struct KeywordMatch {
     pub source_url: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CrawlRequest {
     pub url: String,
     pub keywords: Vec<String>,
     pub max_depth: Option<usize>,
     pub max_time_seconds: Option<u64>,
     pub follow_pagination: Option<bool>,
+    pub max_pagination_pages: Option<usize>,
     pub max_pages: Option<usize>,
     pub date_from: Option<String>,
     pub date_to: Option<String>,
+    pub languages: Option<Vec<String>>,
+    pub similarity_threshold: Option<f32>,
+    pub tracking_params: Option<Vec<String>>,
+    pub include_url_patterns: Option<Vec<String>>,
+    pub exclude_url_patterns: Option<Vec<String>>,
+    pub render_js: Option<bool>,
+    pub wait_for_selector: Option<String>,
+    pub wait_for_idle_ms: Option<u64>,
+    pub only_changed: Option<bool>,
 }
 
-// Helper function to parse multiple URLs from comma-separated string
-fn parse_urls(url_string: &str) -> Result<Vec<Url>, CrawlerError> {
+// Discovered links the outbound policy refuses are rewritten to this blacklisted placeholder, so
+// Spider drops them instead of queueing them
+const BLOCKED_LINK: &str = "http://blocked.invalid/";
+
+// Default time to wait for network idle when rendering pages with headless Chrome
+#[cfg(feature = "chrome")]
+const DEFAULT_JS_IDLE_TIMEOUT_MS: u64 = 5000;
+
+// Helper function to parse multiple URLs from comma-separated string into canonical, de-duplicated seeds
+fn parse_urls(url_string: &str, tracking_params: &[String]) -> Result<Vec<Url>, CrawlerError> {
     let mut urls = Vec::new();
+    let mut seen = HashSet::new();
     
     for url_part in url_string.split(',') {
         let trimmed_url = url_part.trim();
@@ -278,20 +351,25 @@ fn parse_urls(url_string: &str) -> Result<Vec<Url>, CrawlerError> {
                 format!("https://{}", trimmed_url)
             };
             
-            match Url::parse(&full_url) {
-                Ok(parsed_url) => urls.push(parsed_url),
+            let parsed_url = match Url::parse(&full_url) {
+                Ok(parsed_url) => parsed_url,
                 Err(e) => {
                     // Try with http:// if https:// failed
                     if full_url.starts_with("https://") {
                         let http_url = full_url.replace("https://", "http://");
                         match Url::parse(&http_url) {
-                            Ok(parsed_url) => urls.push(parsed_url),
+                            Ok(parsed_url) => parsed_url,
                             Err(_) => return Err(CrawlerError::UrlError(e)),
                         }
                     } else {
                         return Err(CrawlerError::UrlError(e));
                     }
                 }
+            };
+            
+            let canonical_url = canonicalize_url(&parsed_url, tracking_params);
+            if seen.insert(canonical_url.to_string()) {
+                urls.push(canonical_url);
             }
         }
     }
@@ -303,28 +381,75 @@ fn parse_urls(url_string: &str) -> Result<Vec<Url>, CrawlerError> {
     Ok(urls)
 }
 
-pub async fn crawl_website(request: &CrawlRequest) -> Result<CrawlResult, CrawlerError> {
+/// Hosts of the seed URLs in a request, used to look up fetch state from previous crawls
+pub fn seed_hosts(request: &CrawlRequest) -> Result<Vec<String>, CrawlerError> {
+    let tracking_params = tracking_params_or_default(request.tracking_params.as_ref());
+    let urls = parse_urls(&request.url, &tracking_params)?;
+    Ok(urls
+        .iter()
+        .filter_map(|url| url.host_str().map(|host| host.to_string()))
+        .collect())
+}
+
+/// Crawl every seed in the request. `time_budget` is a hard deadline for the whole crawl that
+/// starts once the first politeness lease is held; when it passes, the pages fetched so far are
+/// still processed and the result is marked `truncated`.
+pub async fn crawl_website(
+    request: &CrawlRequest,
+    previous: &PreviousFetchStates,
+    http: &HttpClients,
+    time_budget: Duration,
+) -> Result<CrawlResult, CrawlerError> {
     let start_processing_time = Instant::now();
     
+    // JavaScript rendering is only available in builds with the `chrome` feature
+    if request.render_js.unwrap_or(false) && !cfg!(feature = "chrome") {
+        return Err(CrawlerError::Other(
+            "render_js requires the backend to be built with the `chrome` feature".to_string(),
+        ));
+    }
+    
     // Validate date range if provided
     let (date_from, date_to) = validate_date_range(request.date_from.as_ref(), request.date_to.as_ref())?;
     
     // Parse multiple URLs from the comma-separated string
-    let urls = parse_urls(&request.url)?;
+    let tracking_params = tracking_params_or_default(request.tracking_params.as_ref());
+    let urls = parse_urls(&request.url, &tracking_params)?;
+    
+    // Refuse seeds that point at private, loopback or metadata addresses
+    for url in &urls {
+        http.policy()
+            .check_url(url)
+            .await
+            .map_err(|e| CrawlerError::BlockedUrl(e.to_string()))?;
+    }
+    
+    // Compile include/exclude URL patterns up front so bad patterns fail the whole request
+    let scope = CrawlScope::new(request.include_url_patterns.as_ref(), request.exclude_url_patterns.as_ref())?;
     
     let mut domain_results = Vec::new();
     let mut total_pages_crawled = 0;
+    let mut deadline = None;
+    let mut truncated = false;
     
     // Process each domain using Spider
     for base_url in urls {
-        let domain_result = crawl_single_domain_with_spider(&base_url, request, start_processing_time, date_from, date_to).await;
+        // Seeds left when the deadline has passed are not crawled at all
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            truncated = true;
+            break;
+        }
+        
+        let domain_result = crawl_single_domain_with_spider(&base_url, request, &scope, previous, http, start_processing_time, time_budget, &mut deadline, date_from, date_to).await;
         
         match domain_result {
             Ok(result) => {
                 total_pages_crawled += result.pages_crawled;
+                truncated = truncated || result.truncated;
                 domain_results.push(result);
             }
             Err(err) => {
+                truncated = truncated || matches!(err, CrawlerError::TimeoutError);
                 // Create an error result for this domain
                 let error_result = DomainResult {
                     url: base_url.to_string(),
@@ -333,8 +458,12 @@ pub async fn crawl_website(request: &CrawlRequest) -> Result<CrawlResult, Crawle
                     matches: Vec::new(),
                     pages_crawled: 0,
                     has_more_pages: false,
+                    truncated: false,
+                    pages: Vec::new(),
+                    excluded_links: Vec::new(),
                     metadata: None,
                     error: Some(err.to_string()),
+                    fetch_states: Vec::new(),
                 };
                 domain_results.push(error_result);
             }
@@ -352,49 +481,311 @@ pub async fn crawl_website(request: &CrawlRequest) -> Result<CrawlResult, Crawle
         total_pages_crawled,
         total_processing_time_ms: start_processing_time.elapsed().as_millis() as u64,
         crawl_timestamp: format!("{}", timestamp),
+        truncated,
     })
 }
 
-async fn crawl_single_domain_with_spider(
-    base_url: &Url,
+// Helper function to create a Spider website configured from the crawl request
+fn build_website(
+    start_url: &Url,
     request: &CrawlRequest,
-    start_processing_time: Instant,
-    date_from: Option<NaiveDate>,
-    date_to: Option<NaiveDate>,
-) -> Result<DomainResult, CrawlerError> {
-    let start_time = Instant::now();
-    let time_limit = request.max_time_seconds.map(Duration::from_secs);
-    
+    scope: &CrawlScope,
+    request_delay: Duration,
+    unchanged: &[FetchState],
+    policy: Arc<OutboundPolicy>,
+) -> Website {
     // Create Spider website instance
-    let mut website = Website::new(base_url.as_str());
+    let mut website = Website::new(start_url.as_str());
     
     // Configure Spider based on request parameters
     if let Some(max_pages) = request.max_pages {
-        website.configuration.budget = Some(spider::hashbrown::HashMap::from([
-            (spider::case_insensitive_string::CaseInsensitiveString::from("*"), max_pages as u32)
-        ]));
+        website.configuration.budget = Some(page_budget(max_pages));
     }
     
     if let Some(max_depth) = request.max_depth {
         website.configuration.depth = max_depth;
     }
     
-    // Set request delay to be respectful
-    website.configuration.delay = 1000; // 1 second delay between requests
+    // Set request delay to be respectful (per-host rate or robots.txt Crawl-delay)
+    website.configuration.delay = request_delay.as_millis() as u64;
+    website.configuration.respect_robots_txt = true;
+    
+    // Never follow redirects off the crawled host; internal targets are checked again per page
+    website.with_redirect_policy(spider::configuration::RedirectPolicy::Strict);
+    
+    // Stay on the seed host: Spider fetches with its own client, outside the outbound guard, so
+    // only the host checked before the crawl may be followed
+    website.configuration.subdomains = false;
+    
+    // Check every discovered link before Spider queues it: only hosts that passed the full
+    // outbound check (the seed's) may be followed, and IP literals and internal names never
+    let checked_hosts: Vec<String> = start_url.host_str().map(|host| host.to_lowercase()).into_iter().collect();
+    website.with_on_link_find_callback(Some(Box::new(move |link, html| {
+        let allowed = Url::parse(link.inner().as_str())
+            .map(|url| policy.allows_link(&url, &checked_hosts))
+            .unwrap_or(false);
+        if allowed {
+            (link, html)
+        } else {
+            (BLOCKED_LINK.into(), html)
+        }
+    })));
+    
+    // Keep excluded paths out of link discovery so they are never fetched
+    if !scope.include.is_empty() {
+        let mut whitelist: Vec<spider::compact_str::CompactString> = scope
+            .include
+            .iter()
+            .map(|pattern| pattern.as_regex().into())
+            .collect();
+        // The start page itself must stay fetchable for discovery even when it is outside the includes
+        whitelist.push(format!("^{}$", regex::escape(start_url.as_str())).into());
+        website.with_whitelist_url(Some(whitelist));
+    }
+    // Pages that answered 304 to a conditional request are not fetched again either, nor are
+    // links the outbound policy refused
+    let blacklist: Vec<spider::compact_str::CompactString> = scope
+        .exclude
+        .iter()
+        .map(|pattern| pattern.as_regex().to_string())
+        .chain(unchanged.iter().map(|state| format!("^{}$", regex::escape(&state.url))))
+        .chain(std::iter::once(format!("^{}$", regex::escape(BLOCKED_LINK))))
+        .map(Into::into)
+        .collect();
+    website.with_blacklist_url(Some(blacklist));
+    
+    // ...but the links they had last time are queued so discovery continues past them
+    let unchanged_links: spider::hashbrown::HashSet<spider::case_insensitive_string::CaseInsensitiveString> = unchanged
+        .iter()
+        .flat_map(|state| state.links.iter())
+        .filter(|link| !unchanged.iter().any(|state| state.url == **link))
+        .map(|link| link.as_str().into())
+        .collect();
+    if !unchanged_links.is_empty() {
+        website.set_extra_links(unchanged_links);
+    }
     
-    // Enable subdomains if needed
-    website.configuration.subdomains = true;
+    // Headless Chrome waits so client-side rendered content is present in the HTML
+    #[cfg(feature = "chrome")]
+    if request.render_js.unwrap_or(false) {
+        let idle_timeout = Duration::from_millis(request.wait_for_idle_ms.unwrap_or(DEFAULT_JS_IDLE_TIMEOUT_MS));
+        website.with_wait_for_idle_network(Some(spider::configuration::WaitForIdleNetwork::new(Some(idle_timeout))));
+        if let Some(selector) = &request.wait_for_selector {
+            website.with_wait_for_selector(Some(spider::configuration::WaitForSelector::new(
+                Some(idle_timeout),
+                selector.clone(),
+            )));
+        }
+    }
     
-    // Scrape the website to get pages with content
+    website
+}
+
+// Helper function to cap how many pages a Spider website fetches in total
+fn page_budget(pages: usize) -> spider::hashbrown::HashMap<spider::case_insensitive_string::CaseInsensitiveString, u32> {
+    spider::hashbrown::HashMap::from([
+        (spider::case_insensitive_string::CaseInsensitiveString::from("*"), pages as u32)
+    ])
+}
+
+// Helper function to fetch pages, through headless Chrome when `render_js` is requested
+#[cfg(feature = "chrome")]
+async fn scrape_website(website: &mut Website, request: &CrawlRequest) {
+    if request.render_js.unwrap_or(false) {
+        website.scrape().await;
+    } else {
+        website.scrape_raw().await;
+    }
+}
+
+// Helper function to fetch pages over plain HTTP (built without the `chrome` feature)
+#[cfg(not(feature = "chrome"))]
+async fn scrape_website(website: &mut Website, _request: &CrawlRequest) {
     website.scrape().await;
+}
+
+// Helper function to follow listing pagination from the seed page. Each "next" page is queued
+// on the crawl's own Spider website, so the articles it links to are crawled like the seed's
+// (within `max_depth` and whatever is left of `remaining_pages`), already fetched pages are not
+// fetched again and robots.txt is not refetched. Pagination itself ignores `max_depth`.
+async fn follow_listing_pages(
+    website: &mut Website,
+    base_url: &Url,
+    seed_pages: &[Page],
+    request: &CrawlRequest,
+    policy: &OutboundPolicy,
+    mut remaining_pages: Option<usize>,
+    start_time: Instant,
+    time_limit: Option<Duration>,
+    deadline: Instant,
+) -> (Vec<Page>, usize) {
+    let max_listing_pages = request.max_pagination_pages.unwrap_or(DEFAULT_MAX_PAGINATION_PAGES);
+    let mut collected = Vec::new();
+    let mut listing_pages_followed = 0;
+    let mut visited = HashSet::from([base_url.to_string()]);
+    let mut fetched: HashSet<String> = seed_pages.iter().map(|page| page.get_url().to_string()).collect();
+    
+    let seed_url = base_url.as_str().trim_end_matches('/');
+    let mut current = seed_pages
+        .iter()
+        .find(|page| page.get_url().trim_end_matches('/') == seed_url)
+        .or_else(|| seed_pages.first())
+        .map(|page| (page.get_url().to_string(), page.get_html()));
+    
+    while listing_pages_followed < max_listing_pages && remaining_pages != Some(0) {
+        if let Some(limit) = time_limit {
+            if start_time.elapsed() > limit {
+                break;
+            }
+        }
+        if Instant::now() >= deadline {
+            break;
+        }
+        
+        let next_url = match current.as_ref().and_then(|(url, html)| find_next_page(html, url)) {
+            Some(next_url) => next_url,
+            None => break,
+        };
+        if !visited.insert(next_url.to_string()) {
+            break;
+        }
+        if policy.check_url(&next_url).await.is_err() {
+            break;
+        }
+        
+        // Queue the listing page on the same website; visited links persist between rounds
+        if let Some(remaining) = remaining_pages {
+            website.configuration.budget = Some(page_budget(remaining));
+        }
+        website.set_extra_links(spider::hashbrown::HashSet::from([
+            spider::case_insensitive_string::CaseInsensitiveString::from(next_url.as_str()),
+        ]));
+        let timed_out = timeout_at(deadline.into(), scrape_website(website, request)).await.is_err();
+        
+        let round_pages: Vec<Page> = website
+            .get_pages()
+            .map(|pages| pages.iter().filter(|page| fetched.insert(page.get_url().to_string())).cloned().collect())
+            .unwrap_or_default();
+        let next_str = next_url.as_str().trim_end_matches('/');
+        current = round_pages
+            .iter()
+            .find(|page| page.get_url().trim_end_matches('/') == next_str)
+            .map(|page| (page.get_url().to_string(), page.get_html()));
+        
+        remaining_pages = remaining_pages.map(|remaining| remaining.saturating_sub(round_pages.len().max(1)));
+        collected.extend(round_pages);
+        listing_pages_followed += 1;
+        if timed_out {
+            break;
+        }
+    }
+    
+    (collected, listing_pages_followed)
+}
+
+async fn crawl_single_domain_with_spider(
+    base_url: &Url,
+    request: &CrawlRequest,
+    scope: &CrawlScope,
+    previous: &PreviousFetchStates,
+    http: &HttpClients,
+    start_processing_time: Instant,
+    time_budget: Duration,
+    crawl_deadline: &mut Option<Instant>,
+    date_from: Option<NaiveDate>,
+    date_to: Option<NaiveDate>,
+) -> Result<DomainResult, CrawlerError> {
+    // Share the site politely with every other crawl in this process: wait for a crawl slot,
+    // then pace requests by this crawl's share of the per-site rate and robots.txt Crawl-delay
+    let host = base_url.host_str().unwrap_or_default().to_string();
+    let host_lease = match *crawl_deadline {
+        Some(deadline) => timeout_at(deadline.into(), politeness().acquire_host(&host))
+            .await
+            .map_err(|_| CrawlerError::TimeoutError)?,
+        None => politeness().acquire_host(&host).await,
+    };
+    
+    // The crawl's hard deadline runs from the first lease, not from the time spent queued for it
+    let deadline = *crawl_deadline.get_or_insert_with(|| Instant::now() + time_budget);
+    let mut truncated = false;
+    let client = http.shared();
+    let request_delay = politeness().request_delay(&client, base_url).await;
+    
+    let start_time = Instant::now();
+    let time_limit = request.max_time_seconds.map(Duration::from_secs);
+    
+    // On incremental recrawls, ask the server which previously seen pages are unchanged. They are
+    // not fetched again: their refreshed fetch state is kept and their stored links seed discovery.
+    let only_changed = request.only_changed.unwrap_or(false);
+    let tracking_params = tracking_params_or_default(request.tracking_params.as_ref());
+    let revalidated = if only_changed {
+        timeout_at(deadline.into(), revalidate_unchanged(&client, previous, base_url))
+            .await
+            .unwrap_or_else(|_| {
+                truncated = true;
+                Vec::new()
+            })
+    } else {
+        Vec::new()
+    };
+    let unchanged: HashSet<String> = revalidated
+        .iter()
+        .filter_map(|state| Url::parse(&state.url).ok())
+        .map(|url| canonicalize_url(&url, &tracking_params).to_string())
+        .collect();
+    let mut unchanged_pages = revalidated.len();
+    
+    // Create and configure the Spider website instance; pagination rounds reuse it
+    let mut website = build_website(base_url, request, scope, request_delay, &revalidated, http.policy().clone());
+    website.persist_links();
+    
+    // Spider's requests use this crawl's share of the site's rate until fetching is done
+    let scrape_share = host_lease.scraping();
+    
+    // Scrape the website to get pages with content; at the deadline, keep whatever was fetched
+    if timeout_at(deadline.into(), scrape_website(&mut website, request)).await.is_err() {
+        truncated = true;
+    }
+    let mut pages = website.get_pages().map(|pages| pages.to_vec());
+    
+    // Follow "next"/"older posts" listing pages and their links with whatever is left of max_pages
+    let mut listing_pages_followed = 0;
+    if request.follow_pagination.unwrap_or(false) && !truncated {
+        let seed_pages = pages.as_deref().unwrap_or_default();
+        let remaining_pages = request.max_pages.map(|max_pages| max_pages.saturating_sub(seed_pages.len()));
+        let (listing_pages, followed) = follow_listing_pages(
+            &mut website,
+            base_url,
+            seed_pages,
+            request,
+            http.policy(),
+            remaining_pages,
+            start_time,
+            time_limit,
+            deadline,
+        )
+        .await;
+        if !listing_pages.is_empty() {
+            pages.get_or_insert_with(Vec::new).extend(listing_pages);
+        }
+        listing_pages_followed = followed;
+    }
+    drop(scrape_share);
+    truncated = truncated || Instant::now() >= deadline;
     
     // Process the scraped pages
-    let pages = website.get_pages();
     let mut all_matches = Vec::new();
+    let mut page_results: Vec<PageResult> = Vec::new();
+    let mut duplicate_detector = DuplicateDetector::new(request.similarity_threshold);
+    let mut duplicates_collapsed = 0;
+    let mut seen_urls = HashSet::new();
+    let mut fetch_states = revalidated;
+    let mut excluded_link_counter = scope.excluded_link_counter();
     let mut full_content = String::new();
     let mut page_title = None;
     let mut pages_crawled = 0;
     let mut has_more_pages = false;
+    let mut outbound_hosts = HostCheckCache::new(http.policy().clone());
     
     if let Some(pages) = pages {
         for (index, page) in pages.iter().enumerate() {
@@ -406,6 +797,19 @@ async fn crawl_single_domain_with_spider(
                 }
             }
             
+            // Drop pages whose URL or redirect target resolves to an internal address by now
+            if !outbound_hosts.allows(page.get_url()).await || !outbound_hosts.allows(page.get_url_final()).await {
+                continue;
+            }
+            
+            // Count links this page exposed that the include/exclude rules kept out of the crawl
+            excluded_link_counter.record_page(scope, &page.get_html(), page.get_url(), base_url);
+            
+            // Pages fetched only for discovery (e.g. the seed) are not reported when out of scope
+            if !scope.is_in_scope(page.get_url()) {
+                continue;
+            }
+            
             // Extract page dates for filtering
             let page_dates = extract_dates_from_page(page);
             
@@ -414,19 +818,83 @@ async fn crawl_single_domain_with_spider(
                 continue;
             }
             
+            // Process page content for language detection and keyword matches
+            let html_content = page.get_html();
+            let cleaned_content = clean_html_text(&html_content);
+            
+            // Skip URL variants (tracking params, fragments, canonical aliases) of pages already processed
+            let page_url = page_dedup_key(&html_content, page.get_url(), base_url, &tracking_params)
+                .map(|url| url.to_string())
+                .unwrap_or_else(|| page.get_url().to_string());
+            if !seen_urls.insert(page_url.clone()) {
+                continue;
+            }
+            
+            // Pages that answered 304 are already counted and recorded, e.g. when reached as an alias
+            let requested_url = Url::parse(page.get_url())
+                .map(|url| canonicalize_url(&url, &tracking_params).to_string())
+                .unwrap_or_else(|_| page.get_url().to_string());
+            if unchanged.contains(&page_url) || unchanged.contains(&requested_url) {
+                continue;
+            }
+            
+            // Remember fetch state for the next incremental crawl and skip unchanged content
+            let header_value = |name: &str| {
+                page.headers
+                    .as_ref()
+                    .and_then(|headers| headers.get(name))
+                    .and_then(|value| value.to_str().ok())
+                    .map(|value| value.to_string())
+            };
+            let hash = content_hash(&cleaned_content);
+            let content_unchanged = previous
+                .get(&page_url)
+                .map(|state| state.content_hash == hash)
+                .unwrap_or(false);
+            fetch_states.push(FetchState {
+                url: page_url.clone(),
+                etag: header_value("etag"),
+                last_modified: header_value("last-modified"),
+                content_hash: hash,
+                links: extract_links(&html_content, page.get_url(), base_url)
+                    .into_iter()
+                    .take(MAX_STORED_LINKS)
+                    .collect(),
+            });
+            if only_changed && content_unchanged {
+                unchanged_pages += 1;
+                continue;
+            }
+            
+            // Detect the page language and skip pages outside the requested languages
+            let content_language = header_value("content-language");
+            let language = detect_page_language(&html_content, content_language.as_deref(), &cleaned_content);
+            if !matches_language_filter(language.as_deref(), request.languages.as_deref()) {
+                continue;
+            }
+            
+            // Collapse near-duplicates (syndicated copies, paginated reprints) into the first kept page
+            let fingerprint = simhash(&cleaned_content);
+            if let Some(kept_index) = fingerprint.and_then(|fp| duplicate_detector.find_duplicate(fp)) {
+                page_results[kept_index].duplicates.push(page_url);
+                duplicates_collapsed += 1;
+                continue;
+            }
+            if let Some(fp) = fingerprint {
+                duplicate_detector.insert(fp, page_results.len());
+            }
+            
+            let this_page_title = page
+                .get_metadata()
+                .as_ref()
+                .and_then(|metadata| metadata.title.as_ref())
+                .map(|title| title.to_string());
+            
             // Extract title from the first matching page
             if page_title.is_none() {
-                if let Some(metadata) = page.get_metadata() {
-                    if let Some(title) = &metadata.title {
-                        page_title = Some(title.to_string());
-                    }
-                }
+                page_title = this_page_title.clone();
             }
             
-            // Process page content for keyword matches
-            let html_content = page.get_html();
-            let cleaned_content = clean_html_text(&html_content);
-            
             // Add to full content
             if !full_content.is_empty() {
                 full_content.push_str("\n\n--- Next Page ---\n\n");
@@ -434,6 +902,7 @@ async fn crawl_single_domain_with_spider(
             full_content.push_str(&cleaned_content);
             
             // Search for keywords in the cleaned content
+            let matches_before_page = all_matches.len();
             for keyword in &request.keywords {
                 let keyword_lower = keyword.to_lowercase();
                 let content_lower = cleaned_content.to_lowercase();
@@ -463,12 +932,20 @@ async fn crawl_single_domain_with_spider(
                             cleaned_text: context,
                             count,
                             relevance_score: Some(relevance_score),
-                            source_url: page.get_url().to_string(),
+                            source_url: page_url.clone(),
                         });
                     }
                 }
             }
             
+            page_results.push(PageResult {
+                url: page_url,
+                title: this_page_title,
+                language,
+                match_count: all_matches.len() - matches_before_page,
+                duplicates: Vec::new(),
+            });
+            
             pages_crawled += 1;
             
             // Check if we've reached max pages
@@ -502,6 +979,9 @@ async fn crawl_single_domain_with_spider(
         },
         last_modified: None, // Could be extracted from first page if needed
         published_date: None, // Could be extracted from first page if needed
+        duplicates_collapsed,
+        pagination_pages_followed: listing_pages_followed,
+        unchanged_pages,
     };
     
     Ok(DomainResult {
@@ -510,8 +990,81 @@ async fn crawl_single_domain_with_spider(
         content: full_content,
         matches: all_matches,
         pages_crawled,
-        has_more_pages,
+        has_more_pages: has_more_pages || truncated,
+        truncated,
+        pages: page_results,
+        excluded_links: excluded_link_counter.into_counts(),
         metadata: Some(metadata),
         error: None,
+        fetch_states,
     })
-}
\ No newline at end of file
+}
+
+#[cfg(all(test, feature = "chrome"))]
+mod tests {
+    use super::*;
+    use axum::{response::Html, routing::get, Router};
+    
+    // A page whose keyword only exists after a script has run
+    const SCRIPTED_PAGE: &str = r#"<!doctype html>
+<html lang="en">
+<head><title>Rendered</title></head>
+<body>
+<div id="app">Loading</div>
+<script>
+setTimeout(function () {
+  var late = document.createElement("p");
+  late.id = "late";
+  late.textContent = "Client side rendered content mentions zephyrine exactly here";
+  document.getElementById("app").appendChild(late);
+}, 300);
+</script>
+</body>
+</html>"#;
+    
+    // Helper function to serve SCRIPTED_PAGE on a local port, returning its URL
+    async fn serve_scripted_page() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route("/", get(|| async { Html(SCRIPTED_PAGE) }));
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{}/", addr)
+    }
+    
+    // Helper function to build a single-page crawl request for `url`
+    fn request(url: &str, render_js: bool) -> CrawlRequest {
+        serde_json::from_value(serde_json::json!({
+            "url": url,
+            "keywords": ["zephyrine"],
+            "max_pages": 1,
+            "max_depth": 1,
+            "render_js": render_js,
+            "wait_for_selector": "#late",
+            "wait_for_idle_ms": 5000,
+        }))
+        .unwrap()
+    }
+    
+    #[tokio::test]
+    async fn render_js_sees_content_injected_by_scripts() {
+        // The guard would otherwise refuse the loopback test server
+        let url = serve_scripted_page().await;
+        let http = HttpClients::with_policy(Arc::new(OutboundPolicy::from_allowlist("127.0.0.1"))).unwrap();
+        
+        let rendered = crawl_website(&request(&url, true), &PreviousFetchStates::new(), &http, Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(rendered.results.len(), 1);
+        assert!(
+            rendered.results[0].matches.iter().any(|m| m.keyword == "zephyrine"),
+            "rendered page should contain the injected keyword"
+        );
+        
+        let raw = crawl_website(&request(&url, false), &PreviousFetchStates::new(), &http, Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert!(raw.results[0].matches.is_empty(), "raw HTML has no injected content");
+    }
+}
@@ -0,0 +1,185 @@
+use regex::Regex;
+use std::sync::OnceLock;
+use url::Url;
+
+/// Listing pages followed when `follow_pagination` is set without `max_pagination_pages`
+pub const DEFAULT_MAX_PAGINATION_PAGES: usize = 5;
+
+// Link texts commonly used for "next page" controls (compared lowercased and trimmed)
+const NEXT_LINK_TEXTS: &[&str] = &[
+    "next",
+    "next page",
+    "next »",
+    "next ›",
+    "next →",
+    "»",
+    "›",
+    "→",
+    "older posts",
+    "older entries",
+    "older articles",
+    "more articles",
+    "load more",
+    "selanjutnya",
+    "berikutnya",
+];
+
+// Query parameters commonly carrying the page number of a listing
+const PAGE_PARAMS: &[&str] = &["page", "p", "paged", "pg"];
+
+// Helper function to resolve an href against the page URL, keeping only http(s) links that stay
+// on the page's host (a listing never continues on another site)
+fn resolve(page_url: &Url, href: &str) -> Option<Url> {
+    page_url
+        .join(href.trim())
+        .ok()
+        .filter(|url| url.scheme() == "http" || url.scheme() == "https")
+        .filter(|url| url.host_str() == page_url.host_str())
+        .filter(|url| url != page_url)
+}
+
+// Helper function for the `/page/N/` path pattern shared by the page number strategies
+fn page_path_regex() -> &'static Regex {
+    static PAGE_PATH: OnceLock<Regex> = OnceLock::new();
+    PAGE_PATH.get_or_init(|| Regex::new(r"/page/(\d+)/?$").unwrap())
+}
+
+// Helper function to find `<link rel="next">` or `<a rel="next">`
+fn rel_next(html: &str, page_url: &Url) -> Option<Url> {
+    static REL_NEXT_TAG: OnceLock<Regex> = OnceLock::new();
+    static HREF: OnceLock<Regex> = OnceLock::new();
+
+    let tag_regex = REL_NEXT_TAG
+        .get_or_init(|| Regex::new(r#"(?i)<(?:link|a)[^>]*\brel\s*=\s*["']?[^"'>]*\bnext\b[^>]*>"#).unwrap());
+    let href_regex = HREF.get_or_init(|| Regex::new(r#"(?i)\bhref\s*=\s*["']([^"']+)["']"#).unwrap());
+
+    tag_regex.find_iter(html).find_map(|tag| {
+        href_regex
+            .captures(tag.as_str())
+            .and_then(|cap| cap.get(1))
+            .and_then(|href| resolve(page_url, href.as_str()))
+    })
+}
+
+// Helper function to find an anchor whose visible text reads like "next" / "older posts"
+fn next_link_by_text(html: &str, page_url: &Url) -> Option<Url> {
+    static ANCHOR: OnceLock<Regex> = OnceLock::new();
+    static TAG: OnceLock<Regex> = OnceLock::new();
+
+    let anchor_regex =
+        ANCHOR.get_or_init(|| Regex::new(r#"(?is)<a[^>]*\bhref\s*=\s*["']([^"']+)["'][^>]*>(.*?)</a>"#).unwrap());
+    let tag_regex = TAG.get_or_init(|| Regex::new(r"(?s)<[^>]*>").unwrap());
+
+    anchor_regex.captures_iter(html).find_map(|cap| {
+        let text = tag_regex.replace_all(cap.get(2)?.as_str(), "");
+        let text = text.replace("&raquo;", "»").replace("&rsaquo;", "›").replace("&nbsp;", " ");
+        let text = text.trim().to_lowercase();
+        if NEXT_LINK_TEXTS.contains(&text.as_str()) {
+            resolve(page_url, cap.get(1)?.as_str())
+        } else {
+            None
+        }
+    })
+}
+
+// Helper function to read the current page number from `?page=N` or `/page/N/`
+fn current_page_number(url: &Url) -> usize {
+    if let Some((_, value)) = url.query_pairs().find(|(name, _)| PAGE_PARAMS.contains(&name.as_ref())) {
+        return value.parse().unwrap_or(1);
+    }
+
+    page_path_regex()
+        .captures(url.path())
+        .and_then(|cap| cap.get(1))
+        .and_then(|n| n.as_str().parse().ok())
+        .unwrap_or(1)
+}
+
+// Helper function to find a link to page N+1 using `?page=N` or `/page/N/` patterns
+fn next_link_by_page_number(html: &str, page_url: &Url) -> Option<Url> {
+    static ANCHOR_HREF: OnceLock<Regex> = OnceLock::new();
+
+    let href_regex = ANCHOR_HREF.get_or_init(|| Regex::new(r#"(?i)<a[^>]*\bhref\s*=\s*["']([^"']+)["']"#).unwrap());
+    let wanted = current_page_number(page_url).checked_add(1)?;
+
+    href_regex
+        .captures_iter(html)
+        .filter_map(|cap| cap.get(1))
+        .filter_map(|href| resolve(page_url, href.as_str()))
+        .find(|link| {
+            let by_query = link
+                .query_pairs()
+                .any(|(name, value)| PAGE_PARAMS.contains(&name.as_ref()) && value.parse::<usize>().ok() == Some(wanted));
+            let by_path = page_path_regex()
+                .captures(link.path())
+                .and_then(|cap| cap.get(1))
+                .and_then(|n| n.as_str().parse::<usize>().ok())
+                == Some(wanted);
+            by_query || by_path
+        })
+}
+
+/// Detect the next listing page linked from a page, if any.
+/// Checks `rel="next"` first, then "next"/"older posts" link texts, then `?page=N` style links;
+/// links to other hosts are ignored by all three.
+pub fn find_next_page(html: &str, page_url: &str) -> Option<Url> {
+    let page_url = Url::parse(page_url).ok()?;
+
+    rel_next(html, &page_url)
+        .or_else(|| next_link_by_text(html, &page_url))
+        .or_else(|| next_link_by_page_number(html, &page_url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "https://blog.example.com/news/";
+
+    #[test]
+    fn rel_next_is_preferred() {
+        let html = r#"<link rel="next" href="/news/page/2/"><a href="?page=9">Next</a>"#;
+
+        assert_eq!(find_next_page(html, PAGE).unwrap().as_str(), "https://blog.example.com/news/page/2/");
+    }
+
+    #[test]
+    fn next_link_text_is_recognized() {
+        let html = r#"<a href="/news/archive-b"><span>Older posts</span></a>"#;
+
+        assert_eq!(find_next_page(html, PAGE).unwrap().as_str(), "https://blog.example.com/news/archive-b");
+        assert_eq!(
+            find_next_page(r#"<a href="/n2">Next &raquo;</a>"#, PAGE).unwrap().as_str(),
+            "https://blog.example.com/n2"
+        );
+    }
+
+    #[test]
+    fn page_number_links_continue_from_the_current_page() {
+        let html = r#"<a href="/news/?page=2">2</a><a href="/news/?page=4">4</a><a href="/news/?page=3">3</a>"#;
+
+        assert_eq!(
+            find_next_page(html, "https://blog.example.com/news/?page=3").unwrap().as_str(),
+            "https://blog.example.com/news/?page=4"
+        );
+        assert_eq!(
+            find_next_page(r#"<a href="/news/page/3/">3</a>"#, "https://blog.example.com/news/page/2/").unwrap().as_str(),
+            "https://blog.example.com/news/page/3/"
+        );
+    }
+
+    #[test]
+    fn next_links_to_other_hosts_are_ignored_by_every_strategy() {
+        assert_eq!(find_next_page(r#"<link rel="next" href="https://ads.example.net/p2">"#, PAGE), None);
+        assert_eq!(find_next_page(r#"<a href="https://ads.example.net/p2">Next</a>"#, PAGE), None);
+        assert_eq!(find_next_page(r#"<a href="https://ads.example.net/news/?page=2">2</a>"#, PAGE), None);
+        // A cross-host rel=next does not hide a same-host "next" link
+        let html = r#"<a rel="next" href="https://ads.example.net/p2">ad</a><a href="/news/?page=2">Next</a>"#;
+        assert_eq!(find_next_page(html, PAGE).unwrap().as_str(), "https://blog.example.com/news/?page=2");
+    }
+
+    #[test]
+    fn links_back_to_the_same_page_are_not_next_pages() {
+        assert_eq!(find_next_page(r#"<a rel="next" href="/news/">Next</a>"#, PAGE), None);
+    }
+}
@@ -0,0 +1,442 @@
+use reqwest::Client;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::{sleep, Duration, Instant};
+use url::Url;
+
+// How long a fetched robots.txt Crawl-delay is trusted before fetching it again
+const ROBOTS_TTL: Duration = Duration::from_secs(3600);
+
+// Timeout for fetching robots.txt
+const ROBOTS_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Upper bound on a Crawl-delay we are willing to honor
+const MAX_CRAWL_DELAY: Duration = Duration::from_secs(60);
+
+// How long an unused site is remembered before its state is dropped
+const IDLE_SITE_TTL: Duration = ROBOTS_TTL;
+
+/// Politeness limits applied to every host, read from the environment once
+#[derive(Debug, Clone)]
+pub struct PolitenessConfig {
+    pub requests_per_second: f64,
+    pub burst: f64,
+    pub max_in_flight: usize,
+}
+
+impl PolitenessConfig {
+    pub fn from_env() -> Self {
+        let requests_per_second = std::env::var("POLITENESS_REQUESTS_PER_SECOND")
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|v| *v > 0.0)
+            .unwrap_or(1.0);
+        let burst = std::env::var("POLITENESS_BURST")
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|v| *v >= 1.0)
+            .unwrap_or(2.0);
+        let max_in_flight = std::env::var("POLITENESS_MAX_IN_FLIGHT")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(1);
+
+        Self {
+            requests_per_second,
+            burst,
+            max_in_flight,
+        }
+    }
+}
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    // Tokens after refilling at `rate` per second since the last refill, capped at `burst`
+    fn refilled(&self, now: Instant, rate: f64, burst: f64) -> f64 {
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        (self.tokens + elapsed * rate).min(burst)
+    }
+}
+
+// Politeness state for one site (registrable domain), shared by all of its hosts
+struct HostState {
+    crawls: Arc<Semaphore>,
+    bucket: Mutex<TokenBucket>,
+    // Crawl-delay per host, since robots.txt is served per host
+    robots: Mutex<HashMap<String, (Option<Duration>, Instant)>>,
+    queued: AtomicUsize,
+    in_flight: AtomicUsize,
+    // Crawls whose Spider scrape is running and using its share of the site's rate
+    scraping: AtomicUsize,
+    last_used: Mutex<Instant>,
+}
+
+/// Current load on one site, exposed on the admin endpoint
+#[derive(Debug, Serialize)]
+pub struct HostQueueStatus {
+    /// Registrable domain the limits apply to, e.g. `example.co.uk` for `www.example.co.uk`
+    pub host: String,
+    pub queued_crawls: usize,
+    pub in_flight_crawls: usize,
+    pub available_tokens: f64,
+    /// Slowest Crawl-delay among the site's hosts
+    pub crawl_delay_ms: Option<u64>,
+}
+
+/// Exclusive-ish access to a site for one domain crawl; released on drop
+pub struct HostLease {
+    host: Arc<HostState>,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl HostLease {
+    /// Mark this crawl's Spider scrape as running until the returned guard is dropped. While it
+    /// runs, its share of the site's rate is taken out of the token bucket other requests use.
+    pub fn scraping(&self) -> ScrapeShare {
+        self.host.scraping.fetch_add(1, Ordering::SeqCst);
+        ScrapeShare {
+            host: self.host.clone(),
+        }
+    }
+}
+
+impl Drop for HostLease {
+    fn drop(&mut self) {
+        self.host.in_flight.fetch_sub(1, Ordering::SeqCst);
+        *self.host.last_used.lock().unwrap() = Instant::now();
+    }
+}
+
+/// A running Spider scrape's claim on the site's rate; released on drop
+pub struct ScrapeShare {
+    host: Arc<HostState>,
+}
+
+impl Drop for ScrapeShare {
+    fn drop(&mut self) {
+        self.host.scraping.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Process-wide per-site politeness shared by ad-hoc crawls, scheduled crawls and jobs. Limits
+/// apply to a registrable domain, so `www.example.com` and `blog.example.com` share them.
+pub struct PolitenessScheduler {
+    config: PolitenessConfig,
+    hosts: Mutex<HashMap<String, Arc<HostState>>>,
+}
+
+/// The shared politeness scheduler
+pub fn politeness() -> &'static PolitenessScheduler {
+    static SCHEDULER: OnceLock<PolitenessScheduler> = OnceLock::new();
+    SCHEDULER.get_or_init(|| PolitenessScheduler::new(PolitenessConfig::from_env()))
+}
+
+impl PolitenessScheduler {
+    pub fn new(config: PolitenessConfig) -> Self {
+        Self {
+            config,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn host_state(&self, host: &str) -> Arc<HostState> {
+        let now = Instant::now();
+        let mut hosts = self.hosts.lock().unwrap();
+
+        // Forget sites nobody has used for a while; a state still referenced elsewhere is kept
+        hosts.retain(|_, state| {
+            Arc::strong_count(state) > 1 || now.duration_since(*state.last_used.lock().unwrap()) < IDLE_SITE_TTL
+        });
+
+        let state = hosts
+            .entry(site_key(host))
+            .or_insert_with(|| {
+                Arc::new(HostState {
+                    crawls: Arc::new(Semaphore::new(self.config.max_in_flight)),
+                    bucket: Mutex::new(TokenBucket {
+                        tokens: self.config.burst,
+                        last_refill: now,
+                    }),
+                    robots: Mutex::new(HashMap::new()),
+                    queued: AtomicUsize::new(0),
+                    in_flight: AtomicUsize::new(0),
+                    scraping: AtomicUsize::new(0),
+                    last_used: Mutex::new(now),
+                })
+            })
+            .clone();
+        *state.last_used.lock().unwrap() = now;
+        state
+    }
+
+    // Share of the site's rate each in-flight crawl's Spider scrape may use, so that
+    // `max_in_flight` concurrent scrapes together stay within `requests_per_second`
+    fn scrape_rate(&self) -> f64 {
+        self.config.requests_per_second / self.config.max_in_flight as f64
+    }
+
+    // Rate at which the token bucket refills: what running Spider scrapes leave over
+    fn bucket_rate(&self, state: &HostState) -> f64 {
+        let free_shares = self.config.max_in_flight.saturating_sub(state.scraping.load(Ordering::SeqCst));
+        self.scrape_rate() * free_shares as f64
+    }
+
+    /// Wait for a crawl slot on this host's site; at most `max_in_flight` crawls hit a site at once
+    pub async fn acquire_host(&self, host: &str) -> HostLease {
+        let state = self.host_state(host);
+        state.queued.fetch_add(1, Ordering::SeqCst);
+        let permit = state
+            .crawls
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("host semaphore is never closed");
+        state.queued.fetch_sub(1, Ordering::SeqCst);
+        state.in_flight.fetch_add(1, Ordering::SeqCst);
+
+        HostLease {
+            host: state,
+            _permit: permit,
+        }
+    }
+
+    /// Wait until the site's token bucket allows one more request
+    pub async fn wait_for_token(&self, host: &str) {
+        let state = self.host_state(host);
+        loop {
+            let wait = {
+                let rate = self.bucket_rate(&state);
+                let mut bucket = state.bucket.lock().unwrap();
+                let now = Instant::now();
+                bucket.tokens = bucket.refilled(now, rate, self.config.burst);
+                bucket.last_refill = now;
+
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    None
+                } else if rate > 0.0 {
+                    Some(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+                } else {
+                    // Every share is taken by running scrapes; check again once one could have finished a request
+                    Some(Duration::from_secs_f64(1.0 / self.scrape_rate()))
+                }
+            };
+
+            match wait {
+                None => return,
+                Some(duration) => sleep(duration).await,
+            }
+        }
+    }
+
+    /// Delay between requests Spider should use for this host: the crawl's share of the site's
+    /// rate, or the robots.txt Crawl-delay when that is slower
+    pub async fn request_delay(&self, client: &Client, base_url: &Url) -> Duration {
+        let rate_delay = Duration::from_secs_f64(1.0 / self.scrape_rate());
+        let crawl_delay = self.crawl_delay(client, base_url).await.unwrap_or_default();
+        rate_delay.max(crawl_delay)
+    }
+
+    // Crawl-delay from the host's robots.txt, cached per host
+    async fn crawl_delay(&self, client: &Client, base_url: &Url) -> Option<Duration> {
+        let host = base_url.host_str()?.to_lowercase();
+        let state = self.host_state(&host);
+
+        if let Some((delay, fetched_at)) = state.robots.lock().unwrap().get(&host) {
+            if fetched_at.elapsed() < ROBOTS_TTL {
+                return *delay;
+            }
+        }
+
+        let delay = fetch_crawl_delay(client, base_url).await;
+        state.robots.lock().unwrap().insert(host, (delay, Instant::now()));
+        delay
+    }
+
+    /// Queue depth and load per site, busiest first
+    pub fn snapshot(&self) -> Vec<HostQueueStatus> {
+        let hosts = self.hosts.lock().unwrap();
+        let now = Instant::now();
+        let mut statuses: Vec<HostQueueStatus> = hosts
+            .iter()
+            .map(|(host, state)| HostQueueStatus {
+                host: host.clone(),
+                queued_crawls: state.queued.load(Ordering::SeqCst),
+                in_flight_crawls: state.in_flight.load(Ordering::SeqCst),
+                available_tokens: state
+                    .bucket
+                    .lock()
+                    .unwrap()
+                    .refilled(now, self.bucket_rate(state), self.config.burst),
+                crawl_delay_ms: state
+                    .robots
+                    .lock()
+                    .unwrap()
+                    .values()
+                    .filter_map(|(delay, _)| *delay)
+                    .max()
+                    .map(|delay| delay.as_millis() as u64),
+            })
+            .collect();
+        statuses.sort_by(|a, b| {
+            (b.queued_crawls + b.in_flight_crawls)
+                .cmp(&(a.queued_crawls + a.in_flight_crawls))
+                .then_with(|| a.host.cmp(&b.host))
+        });
+        statuses
+    }
+}
+
+// Helper function to key politeness state by registrable domain (`www.example.co.uk` becomes
+// `example.co.uk`); IP addresses and names without a public suffix are used as they are
+fn site_key(host: &str) -> String {
+    let host = host.trim_end_matches('.').to_lowercase();
+    if host.parse::<std::net::IpAddr>().is_ok() {
+        return host;
+    }
+    psl::domain_str(&host).map(|domain| domain.to_string()).unwrap_or(host)
+}
+
+// Helper function to fetch robots.txt and read the Crawl-delay for `User-agent: *`
+async fn fetch_crawl_delay(client: &Client, base_url: &Url) -> Option<Duration> {
+    let robots_url = base_url.join("/robots.txt").ok()?;
+    let resp = client.get(robots_url).timeout(ROBOTS_TIMEOUT).send().await.ok()?;
+    if !resp.status().is_success() {
+        return None;
+    }
+    let body = resp.text().await.ok()?;
+    parse_crawl_delay(&body)
+}
+
+// Helper function to parse the Crawl-delay that applies to all user agents
+fn parse_crawl_delay(robots_txt: &str) -> Option<Duration> {
+    let mut in_wildcard_group = false;
+    let mut previous_was_agent = false;
+
+    for line in robots_txt.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field.trim().to_lowercase(), value.trim()),
+            None => continue,
+        };
+
+        match field.as_str() {
+            "user-agent" => {
+                // Consecutive User-agent lines share one group
+                if !previous_was_agent {
+                    in_wildcard_group = false;
+                }
+                in_wildcard_group = in_wildcard_group || value == "*";
+                previous_was_agent = true;
+            }
+            "crawl-delay" => {
+                previous_was_agent = false;
+                if in_wildcard_group {
+                    if let Ok(seconds) = value.parse::<f64>() {
+                        if seconds > 0.0 {
+                            return Some(Duration::from_secs_f64(seconds).min(MAX_CRAWL_DELAY));
+                        }
+                    }
+                }
+            }
+            _ => previous_was_agent = false,
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler(max_in_flight: usize) -> PolitenessScheduler {
+        PolitenessScheduler::new(PolitenessConfig {
+            requests_per_second: 1.0,
+            burst: 2.0,
+            max_in_flight,
+        })
+    }
+
+    #[test]
+    fn crawl_delay_applies_to_the_wildcard_group_only() {
+        let robots = "User-agent: Googlebot\nCrawl-delay: 30\n\nUser-agent: *\nDisallow: /admin\nCrawl-delay: 5\n";
+        assert_eq!(parse_crawl_delay(robots), Some(Duration::from_secs(5)));
+
+        let robots = "User-agent: Bingbot\nCrawl-delay: 10\n";
+        assert_eq!(parse_crawl_delay(robots), None);
+    }
+
+    #[test]
+    fn crawl_delay_is_shared_by_consecutive_user_agents() {
+        let robots = "User-agent: Bingbot\nUser-agent: *\nCrawl-delay: 2\n";
+        assert_eq!(parse_crawl_delay(robots), Some(Duration::from_secs(2)));
+
+        // A new group starts after a non-agent line, so the wildcard does not leak into it
+        let robots = "User-agent: *\nDisallow: /tmp\nUser-agent: Bingbot\nCrawl-delay: 9\n";
+        assert_eq!(parse_crawl_delay(robots), None);
+    }
+
+    #[test]
+    fn crawl_delay_parses_fractions_comments_and_case() {
+        let robots = "# robots\nUSER-AGENT: * # everyone\ncrawl-delay: 0.5 # half a second\n";
+        assert_eq!(parse_crawl_delay(robots), Some(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn crawl_delay_ignores_invalid_values_and_caps_large_ones() {
+        assert_eq!(parse_crawl_delay("User-agent: *\nCrawl-delay: soon\n"), None);
+        assert_eq!(parse_crawl_delay("User-agent: *\nCrawl-delay: 0\n"), None);
+        assert_eq!(parse_crawl_delay("User-agent: *\nCrawl-delay: -3\n"), None);
+        assert_eq!(parse_crawl_delay("User-agent: *\nCrawl-delay: 86400\n"), Some(MAX_CRAWL_DELAY));
+        assert_eq!(parse_crawl_delay(""), None);
+    }
+
+    #[test]
+    fn site_key_uses_the_registrable_domain() {
+        assert_eq!(site_key("www.example.com"), "example.com");
+        assert_eq!(site_key("blog.example.com"), "example.com");
+        assert_eq!(site_key("WWW.Example.co.uk."), "example.co.uk");
+        assert_eq!(site_key("example.co.uk"), "example.co.uk");
+    }
+
+    #[test]
+    fn site_key_keeps_ips_and_bare_names() {
+        assert_eq!(site_key("192.168.1.10"), "192.168.1.10");
+        assert_eq!(site_key("::1"), "::1");
+        assert_eq!(site_key("localhost"), "localhost");
+    }
+
+    #[tokio::test]
+    async fn subdomains_share_one_crawl_slot() {
+        let politeness = scheduler(1);
+        let _lease = politeness.acquire_host("www.example.com").await;
+
+        let blocked = tokio::time::timeout(Duration::from_millis(50), politeness.acquire_host("blog.example.com")).await;
+        assert!(blocked.is_err(), "a second host of the same site must wait for the slot");
+
+        let other = tokio::time::timeout(Duration::from_millis(50), politeness.acquire_host("www.example.org")).await;
+        assert!(other.is_ok(), "another site has its own slot");
+    }
+
+    #[tokio::test]
+    async fn snapshot_reports_load_per_site() {
+        let politeness = scheduler(2);
+        let _first = politeness.acquire_host("www.example.com").await;
+        let _second = politeness.acquire_host("shop.example.com").await;
+
+        let snapshot = politeness.snapshot();
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot[0].host, "example.com");
+        assert_eq!(snapshot[0].in_flight_crawls, 2);
+        assert_eq!(snapshot[0].queued_crawls, 0);
+    }
+}
@@ -0,0 +1,264 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use url::Url;
+
+use super::CrawlerError;
+
+// Prefix marking a pattern as a raw regular expression instead of a glob
+const REGEX_PREFIX: &str = "regex:";
+
+/// A compiled include/exclude rule, matched against the full URL
+pub struct UrlPattern {
+    pub source: String,
+    regex: Regex,
+}
+
+impl UrlPattern {
+    /// Compile `regex:<expr>` as a regular expression, anything else as a glob.
+    /// Globs starting with `/` are prefixes of the path; other globs match anywhere in the URL.
+    pub fn parse(pattern: &str) -> Result<Self, CrawlerError> {
+        let expression = match pattern.strip_prefix(REGEX_PREFIX) {
+            Some(expr) => expr.to_string(),
+            None => glob_to_regex(pattern),
+        };
+
+        let regex = Regex::new(&expression)
+            .map_err(|e| CrawlerError::PatternError(format!("Invalid URL pattern '{}': {}", pattern, e)))?;
+
+        Ok(Self {
+            source: pattern.to_string(),
+            regex,
+        })
+    }
+
+    pub fn is_match(&self, url: &str) -> bool {
+        self.regex.is_match(url)
+    }
+
+    /// Regular expression handed to Spider's whitelist/blacklist
+    pub fn as_regex(&self) -> &str {
+        self.regex.as_str()
+    }
+}
+
+// Helper function to translate a URL glob into a regular expression over the full URL.
+// Like robots.txt rules: a leading `/` anchors at the start of the path, `*` matches anything
+// and a trailing `$` anchors at the end of the URL.
+fn glob_to_regex(glob: &str) -> String {
+    let (body, anchored_end) = match glob.strip_suffix('$') {
+        Some(body) => (body, true),
+        None => (glob, false),
+    };
+
+    let mut expression = String::new();
+    if body.starts_with('/') {
+        expression.push_str(r"^[a-zA-Z][a-zA-Z0-9+.-]*://[^/]+");
+    }
+
+    for c in body.chars() {
+        match c {
+            '*' => expression.push_str(".*"),
+            _ => expression.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    if anchored_end {
+        expression.push('$');
+    }
+
+    expression
+}
+
+/// How many discovered links a single rule kept out of the crawl
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExcludedLinkCount {
+    pub rule: String,
+    pub count: usize,
+}
+
+/// Include/exclude URL patterns for one crawl request
+pub struct CrawlScope {
+    pub include: Vec<UrlPattern>,
+    pub exclude: Vec<UrlPattern>,
+}
+
+// Rule name reported for links that matched none of the include patterns
+const NOT_INCLUDED_RULE: &str = "include_url_patterns";
+
+impl CrawlScope {
+    pub fn new(include: Option<&Vec<String>>, exclude: Option<&Vec<String>>) -> Result<Self, CrawlerError> {
+        let compile = |patterns: Option<&Vec<String>>| -> Result<Vec<UrlPattern>, CrawlerError> {
+            patterns
+                .map(|patterns| patterns.iter().map(|p| UrlPattern::parse(p)).collect())
+                .unwrap_or_else(|| Ok(Vec::new()))
+        };
+
+        Ok(Self {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Name of the rule that keeps this URL out of scope, if any
+    pub fn excluding_rule(&self, url: &str) -> Option<&str> {
+        if let Some(pattern) = self.exclude.iter().find(|pattern| pattern.is_match(url)) {
+            return Some(&pattern.source);
+        }
+
+        if !self.include.is_empty() && !self.include.iter().any(|pattern| pattern.is_match(url)) {
+            return Some(NOT_INCLUDED_RULE);
+        }
+
+        None
+    }
+
+    pub fn is_in_scope(&self, url: &str) -> bool {
+        self.excluding_rule(url).is_none()
+    }
+
+    /// Start counting the links each rule keeps out of the crawl
+    pub fn excluded_link_counter(&self) -> ExcludedLinkCounter {
+        let counts = self
+            .exclude
+            .iter()
+            .map(|pattern| pattern.source.clone())
+            .chain((!self.include.is_empty()).then(|| NOT_INCLUDED_RULE.to_string()))
+            .map(|rule| ExcludedLinkCount { rule, count: 0 })
+            .collect();
+
+        ExcludedLinkCounter {
+            seen: HashSet::new(),
+            counts,
+        }
+    }
+}
+
+/// Counts distinct discovered links per excluding rule across the pages of one domain
+pub struct ExcludedLinkCounter {
+    seen: HashSet<String>,
+    counts: Vec<ExcludedLinkCount>,
+}
+
+impl ExcludedLinkCounter {
+    pub fn record_page(&mut self, scope: &CrawlScope, html: &str, page_url: &str, base_url: &Url) {
+        if scope.is_empty() {
+            return;
+        }
+
+        for link in extract_links(html, page_url, base_url) {
+            if !self.seen.insert(link.clone()) {
+                continue;
+            }
+            if let Some(rule) = scope.excluding_rule(&link) {
+                if let Some(entry) = self.counts.iter_mut().find(|entry| entry.rule == rule) {
+                    entry.count += 1;
+                }
+            }
+        }
+    }
+
+    pub fn into_counts(self) -> Vec<ExcludedLinkCount> {
+        self.counts
+    }
+}
+
+/// Extract same-site links from a page, the way Spider would discover them
+pub fn extract_links(html: &str, page_url: &str, base_url: &Url) -> Vec<String> {
+    static HREF: OnceLock<Regex> = OnceLock::new();
+
+    let href_regex = HREF.get_or_init(|| Regex::new(r#"(?i)<a[^>]*\shref\s*=\s*["']([^"'#]+)"#).unwrap());
+    let page_url = match Url::parse(page_url) {
+        Ok(url) => url,
+        Err(_) => return Vec::new(),
+    };
+    let base_host = base_url.host_str().unwrap_or("").trim_start_matches("www.");
+
+    href_regex
+        .captures_iter(html)
+        .filter_map(|cap| cap.get(1))
+        .filter_map(|href| page_url.join(href.as_str().trim()).ok())
+        .filter(|link| link.scheme() == "http" || link.scheme() == "https")
+        .filter(|link| {
+            link.host_str()
+                .map(|host| host.trim_start_matches("www.") == base_host || host.ends_with(&format!(".{}", base_host)))
+                .unwrap_or(false)
+        })
+        .map(|link| link.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn path_globs_are_anchored_at_the_start_of_the_path() {
+        let pattern = UrlPattern::parse("/blog/*").unwrap();
+
+        assert!(pattern.is_match("https://example.com/blog/post-1"));
+        assert!(!pattern.is_match("https://example.com/en/blog/post-1"));
+        assert!(!pattern.is_match("https://example.com/blogroll"));
+    }
+
+    #[test]
+    fn other_globs_match_anywhere_and_dollar_anchors_the_end() {
+        let anywhere = UrlPattern::parse("*tag=*").unwrap();
+        let pdf = UrlPattern::parse("*.pdf$").unwrap();
+
+        assert!(anywhere.is_match("https://example.com/search?tag=rust"));
+        assert!(pdf.is_match("https://example.com/files/report.pdf"));
+        assert!(!pdf.is_match("https://example.com/files/report.pdf?download=1"));
+        // Regex metacharacters in globs are literal
+        assert!(!UrlPattern::parse("/a.b").unwrap().is_match("https://example.com/axb"));
+    }
+
+    #[test]
+    fn regex_patterns_are_used_as_written() {
+        let pattern = UrlPattern::parse(r"regex:/\d{4}/\d{2}/").unwrap();
+
+        assert!(pattern.is_match("https://example.com/2024/05/story"));
+        assert!(!pattern.is_match("https://example.com/archive/story"));
+        assert!(UrlPattern::parse("regex:(unclosed").is_err());
+    }
+
+    #[test]
+    fn exclude_rules_win_over_include_rules() {
+        let scope = CrawlScope::new(Some(&patterns(&["/news/*"])), Some(&patterns(&["/news/archive/*"]))).unwrap();
+
+        assert!(scope.is_in_scope("https://example.com/news/today"));
+        assert_eq!(scope.excluding_rule("https://example.com/news/archive/2001"), Some("/news/archive/*"));
+        assert_eq!(scope.excluding_rule("https://example.com/about"), Some(NOT_INCLUDED_RULE));
+        assert!(CrawlScope::new(None, None).unwrap().is_in_scope("https://example.com/anything"));
+    }
+
+    #[test]
+    fn excluded_links_are_counted_once_per_rule() {
+        let scope = CrawlScope::new(Some(&patterns(&["/news/*"])), Some(&patterns(&["*/print"]))).unwrap();
+        let base_url = Url::parse("https://example.com/news/").unwrap();
+        let html = r#"
+            <a href="/news/a">A</a>
+            <a href="/news/a/print">print</a>
+            <a class="x" href="/news/a/print#top">print again</a>
+            <a href="https://www.example.com/about">about</a>
+            <a href="https://other.example.org/news/b">elsewhere</a>
+            <a href="mailto:desk@example.com">mail</a>
+        "#;
+
+        let mut counter = scope.excluded_link_counter();
+        counter.record_page(&scope, html, "https://example.com/news/", &base_url);
+        counter.record_page(&scope, html, "https://example.com/news/", &base_url);
+        let counts: Vec<(String, usize)> = counter.into_counts().into_iter().map(|c| (c.rule, c.count)).collect();
+
+        assert_eq!(counts, vec![("*/print".to_string(), 1), (NOT_INCLUDED_RULE.to_string(), 1)]);
+    }
+}
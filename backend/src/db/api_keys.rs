@@ -0,0 +1,155 @@
+use chrono::{DateTime, TimeZone, Utc};
+use scylla::frame::value::{Counter, CqlTimestamp};
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use uuid::Uuid;
+
+use crate::auth::ApiKey;
+
+const API_KEY_COLUMNS: &str =
+    "key_hash, id, name, key_prefix, rate_limit_per_minute, crawl_monthly_quota, social_monthly_quota, revoked, created_at";
+
+type ApiKeyRow = (
+    String,
+    Uuid,
+    Option<String>,
+    Option<String>,
+    Option<i32>,
+    Option<i64>,
+    Option<i64>,
+    Option<bool>,
+    Option<CqlTimestamp>,
+);
+
+fn from_cql(ts: CqlTimestamp) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(ts.0).single().unwrap_or_default()
+}
+
+// Rows keep their hash so revocation can address the partition
+fn row_to_api_key(row: ApiKeyRow) -> (String, ApiKey) {
+    let (key_hash, id, name, key_prefix, rate_limit_per_minute, crawl_monthly_quota, social_monthly_quota, revoked, created_at) = row;
+    let api_key = ApiKey {
+        id,
+        name: name.unwrap_or_default(),
+        key_prefix: key_prefix.unwrap_or_default(),
+        rate_limit_per_minute: rate_limit_per_minute.unwrap_or_default().max(0) as u32,
+        crawl_monthly_quota,
+        social_monthly_quota,
+        revoked: revoked.unwrap_or(false),
+        created_at: created_at.map(from_cql).unwrap_or_default(),
+    };
+    (key_hash, api_key)
+}
+
+pub async fn insert_api_key(session: &Session, keyspace: &str, key_hash: &str, api_key: &ApiKey) -> Result<(), QueryError> {
+    let query = format!(
+        "INSERT INTO {}.api_keys ({}) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        keyspace, API_KEY_COLUMNS
+    );
+    session
+        .query(
+            query,
+            (
+                key_hash,
+                api_key.id,
+                &api_key.name,
+                &api_key.key_prefix,
+                api_key.rate_limit_per_minute as i32,
+                api_key.crawl_monthly_quota,
+                api_key.social_monthly_quota,
+                api_key.revoked,
+                CqlTimestamp(api_key.created_at.timestamp_millis()),
+            ),
+        )
+        .await
+        .map(|_| ())
+}
+
+pub async fn get_api_key_by_hash(session: &Session, keyspace: &str, key_hash: &str) -> Result<Option<ApiKey>, QueryError> {
+    let query = format!("SELECT {} FROM {}.api_keys WHERE key_hash = ?", API_KEY_COLUMNS, keyspace);
+    let result = session.query(query, (key_hash,)).await?;
+    Ok(result
+        .rows_typed_or_empty::<ApiKeyRow>()
+        .filter_map(|row| row.ok())
+        .map(|row| row_to_api_key(row).1)
+        .next())
+}
+
+pub async fn list_api_keys(session: &Session, keyspace: &str) -> Result<Vec<ApiKey>, QueryError> {
+    let query = format!("SELECT {} FROM {}.api_keys", API_KEY_COLUMNS, keyspace);
+    let result = session.query(query, &[]).await?;
+    Ok(result
+        .rows_typed_or_empty::<ApiKeyRow>()
+        .filter_map(|row| row.ok())
+        .map(|row| row_to_api_key(row).1)
+        .collect())
+}
+
+/// Mark a key revoked. Returns the key, or `None` when no key has this id.
+pub async fn revoke_api_key(session: &Session, keyspace: &str, id: Uuid) -> Result<Option<ApiKey>, QueryError> {
+    // `id` has a secondary index; the partition key is the hash
+    let query = format!("SELECT {} FROM {}.api_keys WHERE id = ?", API_KEY_COLUMNS, keyspace);
+    let result = session.query(query, (id,)).await?;
+    let (key_hash, mut api_key) = match result
+        .rows_typed_or_empty::<ApiKeyRow>()
+        .filter_map(|row| row.ok())
+        .map(row_to_api_key)
+        .next()
+    {
+        Some(found) => found,
+        None => return Ok(None),
+    };
+
+    let query = format!("UPDATE {}.api_keys SET revoked = true WHERE key_hash = ?", keyspace);
+    session.query(query, (key_hash,)).await?;
+    api_key.revoked = true;
+    Ok(Some(api_key))
+}
+
+pub async fn increment_api_key_usage(
+    session: &Session,
+    keyspace: &str,
+    key_id: Uuid,
+    month: &str,
+    scope: &str,
+) -> Result<(), QueryError> {
+    let query = format!(
+        "UPDATE {}.api_key_usage SET requests = requests + 1 WHERE key_id = ? AND month = ? AND scope = ?",
+        keyspace
+    );
+    session.query(query, (key_id, month, scope)).await.map(|_| ())
+}
+
+/// Give back a request counted by `increment_api_key_usage`, e.g. when it was rejected over quota
+pub async fn decrement_api_key_usage(
+    session: &Session,
+    keyspace: &str,
+    key_id: Uuid,
+    month: &str,
+    scope: &str,
+) -> Result<(), QueryError> {
+    let query = format!(
+        "UPDATE {}.api_key_usage SET requests = requests - 1 WHERE key_id = ? AND month = ? AND scope = ?",
+        keyspace
+    );
+    session.query(query, (key_id, month, scope)).await.map(|_| ())
+}
+
+/// Requests per scope for a key in one month
+pub async fn get_api_key_usage(
+    session: &Session,
+    keyspace: &str,
+    key_id: Uuid,
+    month: &str,
+) -> Result<Vec<(String, i64)>, QueryError> {
+    let query = format!(
+        "SELECT scope, requests FROM {}.api_key_usage WHERE key_id = ? AND month = ?",
+        keyspace
+    );
+    let result = session.query(query, (key_id, month)).await?;
+    Ok(result
+        .rows_typed_or_empty::<(String, Counter)>()
+        .filter_map(|row| row.ok())
+        .map(|(scope, requests)| (scope, requests.0))
+        .collect())
+}
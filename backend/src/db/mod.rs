@@ -2,6 +2,41 @@ use scylla::{Session, SessionBuilder};
 use std::sync::Arc;
 use tokio::time::{sleep, Duration};
 
+mod api_keys;
+pub use api_keys::{
+    decrement_api_key_usage,
+    get_api_key_by_hash,
+    get_api_key_usage,
+    increment_api_key_usage,
+    insert_api_key,
+    list_api_keys,
+    revoke_api_key,
+};
+mod schedules;
+pub use schedules::{
+    claim_schedule_run,
+    delete_schedule,
+    extend_schedule_lock,
+    get_schedule,
+    insert_schedule,
+    list_schedules,
+    record_schedule_run,
+    update_schedule,
+};
+mod social_posts;
+pub use social_posts::insert_social_posts;
+mod webhooks;
+pub use webhooks::{
+    claim_webhook_alert,
+    delete_webhook,
+    finish_webhook_alert,
+    get_webhook,
+    insert_webhook,
+    insert_webhook_delivery,
+    list_webhook_deliveries,
+    list_webhooks,
+};
+
 #[derive(Clone)]
 pub struct CassandraState {
     pub session: Arc<Session>,
@@ -72,27 +107,225 @@ pub async fn init_db() -> Result<CassandraState, scylla::transport::errors::NewS
         )
         .await;
 
+    // Columns added later: upstream status and response cache key, indexed for cache lookups
+    for column in ["status int", "cache_key text"] {
+        let _ = session
+            .query(format!("ALTER TABLE {}.social_results ADD {}", keyspace, column), &[])
+            .await;
+    }
+    let _ = session
+        .query(
+            format!("CREATE INDEX IF NOT EXISTS social_results_cache_key_idx ON {}.social_results (cache_key)", keyspace),
+            &[]
+        )
+        .await;
+
+    // Ensure table exists for posts normalized out of social proxy results
+    let _ = session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.social_posts (platform text, post_id text, source text, author text, text text, created_at timestamp, likes bigint, comments bigint, shares bigint, views bigint, media_urls list<text>, permalink text, fetched_at timestamp, PRIMARY KEY ((platform, post_id)))",
+                keyspace
+            ),
+            &[]
+        )
+        .await;
+
+    // Ensure table exists for per-URL fetch state used by incremental recrawls
+    let _ = session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.page_fetch_state (host text, url text, etag text, last_modified text, content_hash text, last_seen timestamp, PRIMARY KEY (host, url))",
+                keyspace
+            ),
+            &[]
+        )
+        .await;
+    // Column added later: same-site links of the page, so unchanged pages need not be refetched
+    let _ = session
+        .query(format!("ALTER TABLE {}.page_fetch_state ADD links list<text>", keyspace), &[])
+        .await;
+
+    // Ensure table exists for recurring crawl schedules
+    let _ = session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.crawl_schedules (id uuid PRIMARY KEY, name text, request text, cron text, interval_seconds bigint, enabled boolean, next_run_at timestamp, last_run_at timestamp, last_error text, last_result_id uuid, locked_by text, created_at timestamp)",
+                keyspace
+            ),
+            &[]
+        )
+        .await;
+    // Column added later: until when the replica in `locked_by` holds a running schedule
+    let _ = session
+        .query(format!("ALTER TABLE {}.crawl_schedules ADD locked_until timestamp", keyspace), &[])
+        .await;
+
+    // Ensure tables exist for webhook subscriptions, sent alerts and the delivery log
+    let _ = session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.webhooks (id uuid PRIMARY KEY, url text, secret text, keywords list<text>, domains list<text>, enabled boolean, created_at timestamp)",
+                keyspace
+            ),
+            &[]
+        )
+        .await;
+    let _ = session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.webhook_alerts (subscription_id uuid, source_url text, keyword text, created_at timestamp, PRIMARY KEY (subscription_id, source_url, keyword))",
+                keyspace
+            ),
+            &[]
+        )
+        .await;
+    // Column added later: delivery status, so alerts that failed are claimed again by later crawls
+    let _ = session
+        .query(format!("ALTER TABLE {}.webhook_alerts ADD status text", keyspace), &[])
+        .await;
+    let _ = session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.webhook_deliveries (subscription_id uuid, id uuid, source_url text, keyword text, attempts int, status_code int, error text, delivered_at timestamp, PRIMARY KEY (subscription_id, id))",
+                keyspace
+            ),
+            &[]
+        )
+        .await;
+
+    // Ensure tables exist for hashed API keys and their monthly usage counters
+    let _ = session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.api_keys (key_hash text PRIMARY KEY, id uuid, name text, key_prefix text, rate_limit_per_minute int, crawl_monthly_quota bigint, social_monthly_quota bigint, revoked boolean, created_at timestamp)",
+                keyspace
+            ),
+            &[]
+        )
+        .await;
+    let _ = session
+        .query(
+            format!("CREATE INDEX IF NOT EXISTS api_keys_id_idx ON {}.api_keys (id)", keyspace),
+            &[]
+        )
+        .await;
+    let _ = session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.api_key_usage (key_id uuid, month text, scope text, requests counter, PRIMARY KEY ((key_id, month), scope))",
+                keyspace
+            ),
+            &[]
+        )
+        .await;
+
     Ok(CassandraState { session: Arc::new(session), keyspace })
 }
 
+use chrono::{DateTime, TimeZone, Utc};
+use scylla::frame::value::CqlTimestamp;
 use scylla::transport::errors::QueryError;
 use uuid::Uuid;
+use crate::crawler::FetchState;
+
+/// One raw social proxy payload to store
+pub struct NewSocialResult {
+    pub source: String,
+    pub request_path: String,
+    pub params_json: Option<String>,
+    pub payload_json: String,
+    pub status: i32,
+    /// Set for cacheable responses so the Cassandra cache tier can find them
+    pub cache_key: Option<String>,
+}
 
 pub async fn insert_social_result(
     session: Arc<Session>,
     keyspace: String,
-    source: String,
-    request_path: String,
-    params_json: Option<String>,
-    payload_json: String,
+    result: NewSocialResult,
 ) -> Result<(), QueryError> {
     let id = Uuid::new_v4();
     let query = format!(
-        "INSERT INTO {}.social_results (id, source, request_path, params, payload, created_at) VALUES (?, ?, ?, ?, ?, toTimestamp(now()))",
+        "INSERT INTO {}.social_results (id, source, request_path, params, payload, status, cache_key, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, toTimestamp(now()))",
+        keyspace
+    );
+    session
+        .query(
+            query,
+            (id, result.source, result.request_path, result.params_json, result.payload_json, result.status, result.cache_key),
+        )
+        .await
+        .map(|_| ())
+}
+
+/// Newest stored payload for a response cache key, with its status and when it was stored
+pub async fn find_cached_social_result(
+    session: &Session,
+    keyspace: &str,
+    cache_key: &str,
+) -> Result<Option<(String, i32, DateTime<Utc>)>, QueryError> {
+    let query = format!(
+        "SELECT payload, status, created_at FROM {}.social_results WHERE cache_key = ?",
+        keyspace
+    );
+    let result = session.query(query, (cache_key,)).await?;
+    Ok(result
+        .rows_typed_or_empty::<(Option<String>, Option<i32>, Option<CqlTimestamp>)>()
+        .filter_map(|row| row.ok())
+        .filter_map(|(payload, status, created_at)| {
+            let created_at = Utc.timestamp_millis_opt(created_at?.0).single()?;
+            Some((payload?, status.unwrap_or(200), created_at))
+        })
+        .max_by_key(|(_, _, created_at)| *created_at))
+}
+
+/// Stored JSON payload of a crawl result
+pub async fn get_crawl_result(session: &Session, keyspace: &str, id: Uuid) -> Result<Option<String>, QueryError> {
+    let query = format!("SELECT payload FROM {}.crawl_results WHERE id = ?", keyspace);
+    let result = session.query(query, (id,)).await?;
+    Ok(result
+        .rows_typed_or_empty::<(Option<String>,)>()
+        .filter_map(|row| row.ok())
+        .find_map(|(payload,)| payload))
+}
+
+pub async fn load_fetch_states(
+    session: &Session,
+    keyspace: &str,
+    host: &str,
+) -> Result<Vec<FetchState>, QueryError> {
+    let query = format!(
+        "SELECT url, etag, last_modified, content_hash, links FROM {}.page_fetch_state WHERE host = ?",
+        keyspace
+    );
+    let result = session.query(query, (host,)).await?;
+    Ok(result
+        .rows_typed_or_empty::<(String, Option<String>, Option<String>, Option<String>, Option<Vec<String>>)>()
+        .filter_map(|row| row.ok())
+        .map(|(url, etag, last_modified, content_hash, links)| FetchState {
+            url,
+            etag,
+            last_modified,
+            content_hash: content_hash.unwrap_or_default(),
+            links: links.unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// Store a page's fetch state; `last_seen` is set to now
+pub async fn upsert_fetch_state(
+    session: &Session,
+    keyspace: &str,
+    state: &FetchState,
+) -> Result<(), QueryError> {
+    let host = state.host().unwrap_or_default();
+    let query = format!(
+        "INSERT INTO {}.page_fetch_state (host, url, etag, last_modified, content_hash, links, last_seen) VALUES (?, ?, ?, ?, ?, ?, toTimestamp(now()))",
         keyspace
     );
     session
-        .query(query, (id, source, request_path, params_json, payload_json))
+        .query(query, (host, &state.url, &state.etag, &state.last_modified, &state.content_hash, &state.links))
         .await
         .map(|_| ())
-}
\ No newline at end of file
+}
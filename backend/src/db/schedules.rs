@@ -0,0 +1,215 @@
+use chrono::{DateTime, TimeZone, Utc};
+use scylla::frame::response::result::CqlValue;
+use scylla::frame::value::CqlTimestamp;
+use scylla::transport::errors::QueryError;
+use scylla::{QueryResult, Session};
+use uuid::Uuid;
+
+use crate::scheduler::Schedule;
+
+const SCHEDULE_COLUMNS: &str =
+    "id, name, request, cron, interval_seconds, enabled, next_run_at, last_run_at, last_error, last_result_id, created_at, locked_until";
+
+type ScheduleRow = (
+    Uuid,
+    Option<String>,
+    String,
+    Option<String>,
+    Option<i64>,
+    Option<bool>,
+    Option<CqlTimestamp>,
+    Option<CqlTimestamp>,
+    Option<String>,
+    Option<Uuid>,
+    Option<CqlTimestamp>,
+    Option<CqlTimestamp>,
+);
+
+fn to_cql(ts: DateTime<Utc>) -> CqlTimestamp {
+    CqlTimestamp(ts.timestamp_millis())
+}
+
+fn from_cql(ts: CqlTimestamp) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(ts.0).single().unwrap_or_default()
+}
+
+fn row_to_schedule(row: ScheduleRow) -> Option<Schedule> {
+    let (id, name, request, cron, interval_seconds, enabled, next_run_at, last_run_at, last_error, last_result_id, created_at, locked_until) =
+        row;
+    let request = match serde_json::from_str(&request) {
+        Ok(request) => request,
+        Err(e) => {
+            eprintln!("[Cassandra] Skipping schedule {} with unreadable request: {}", id, e);
+            return None;
+        }
+    };
+
+    Some(Schedule {
+        id,
+        name,
+        request,
+        cron,
+        interval_seconds: interval_seconds.map(|s| s as u64),
+        enabled: enabled.unwrap_or(true),
+        next_run_at: next_run_at.map(from_cql).unwrap_or_default(),
+        last_run_at: last_run_at.map(from_cql),
+        last_error,
+        last_result_id,
+        created_at: created_at.map(from_cql).unwrap_or_default(),
+        running_until: locked_until.map(from_cql),
+    })
+}
+
+// The first column of an LWT result is the `[applied]` flag
+fn lwt_applied(result: QueryResult) -> bool {
+    result
+        .first_row()
+        .ok()
+        .and_then(|row| row.columns.into_iter().next().flatten())
+        .map(|value| matches!(value, CqlValue::Boolean(true)))
+        .unwrap_or(false)
+}
+
+// Every write to `next_run_at` (and to the run lock) is a lightweight transaction: Cassandra does
+// not order plain writes against LWTs on the same cell, so mixing them could lose or repeat a run.
+
+/// Store a new schedule. Returns `false` if a schedule with this id already exists.
+pub async fn insert_schedule(session: &Session, keyspace: &str, schedule: &Schedule) -> Result<bool, QueryError> {
+    let request = serde_json::to_string(&schedule.request).unwrap_or_default();
+    let query = format!(
+        "INSERT INTO {}.crawl_schedules (id, name, request, cron, interval_seconds, enabled, next_run_at, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?) IF NOT EXISTS",
+        keyspace
+    );
+    let result = session
+        .query(
+            query,
+            (
+                schedule.id,
+                &schedule.name,
+                request,
+                &schedule.cron,
+                schedule.interval_seconds.map(|s| s as i64),
+                schedule.enabled,
+                to_cql(schedule.next_run_at),
+                to_cql(schedule.created_at),
+            ),
+        )
+        .await?;
+    Ok(lwt_applied(result))
+}
+
+/// Replace a schedule's definition and cadence, provided `next_run_at` is still what the caller
+/// read. Returns `false` when a replica claimed a run (or another edit landed) in between.
+pub async fn update_schedule(
+    session: &Session,
+    keyspace: &str,
+    schedule: &Schedule,
+    expected_next_run_at: DateTime<Utc>,
+) -> Result<bool, QueryError> {
+    let request = serde_json::to_string(&schedule.request).unwrap_or_default();
+    let query = format!(
+        "UPDATE {}.crawl_schedules SET name = ?, request = ?, cron = ?, interval_seconds = ?, enabled = ?, next_run_at = ? WHERE id = ? IF next_run_at = ?",
+        keyspace
+    );
+    let result = session
+        .query(
+            query,
+            (
+                &schedule.name,
+                request,
+                &schedule.cron,
+                schedule.interval_seconds.map(|s| s as i64),
+                schedule.enabled,
+                to_cql(schedule.next_run_at),
+                schedule.id,
+                to_cql(expected_next_run_at),
+            ),
+        )
+        .await?;
+    Ok(lwt_applied(result))
+}
+
+pub async fn get_schedule(session: &Session, keyspace: &str, id: Uuid) -> Result<Option<Schedule>, QueryError> {
+    let query = format!("SELECT {} FROM {}.crawl_schedules WHERE id = ?", SCHEDULE_COLUMNS, keyspace);
+    let result = session.query(query, (id,)).await?;
+    Ok(result
+        .rows_typed_or_empty::<ScheduleRow>()
+        .filter_map(|row| row.ok())
+        .find_map(row_to_schedule))
+}
+
+pub async fn list_schedules(session: &Session, keyspace: &str) -> Result<Vec<Schedule>, QueryError> {
+    let query = format!("SELECT {} FROM {}.crawl_schedules", SCHEDULE_COLUMNS, keyspace);
+    let result = session.query(query, &[]).await?;
+    Ok(result
+        .rows_typed_or_empty::<ScheduleRow>()
+        .filter_map(|row| row.ok())
+        .filter_map(row_to_schedule)
+        .collect())
+}
+
+pub async fn delete_schedule(session: &Session, keyspace: &str, id: Uuid) -> Result<(), QueryError> {
+    let query = format!("DELETE FROM {}.crawl_schedules WHERE id = ? IF EXISTS", keyspace);
+    session.query(query, (id,)).await.map(|_| ())
+}
+
+/// Claim a due run by moving `next_run_at` forward and taking the run lock until `locked_until`,
+/// with a lightweight transaction. Returns `true` only for the replica whose compare-and-set was
+/// applied. Callers skip schedules whose lock has not expired yet.
+pub async fn claim_schedule_run(
+    session: &Session,
+    keyspace: &str,
+    id: Uuid,
+    expected_next_run_at: DateTime<Utc>,
+    next_run_at: DateTime<Utc>,
+    owner: &str,
+    locked_until: DateTime<Utc>,
+) -> Result<bool, QueryError> {
+    let query = format!(
+        "UPDATE {}.crawl_schedules SET next_run_at = ?, locked_by = ?, locked_until = ? WHERE id = ? IF next_run_at = ?",
+        keyspace
+    );
+    let result = session
+        .query(
+            query,
+            (to_cql(next_run_at), owner, to_cql(locked_until), id, to_cql(expected_next_run_at)),
+        )
+        .await?;
+    Ok(lwt_applied(result))
+}
+
+/// Record the outcome of a run and release the lock `owner` took when claiming it
+pub async fn record_schedule_run(
+    session: &Session,
+    keyspace: &str,
+    id: Uuid,
+    owner: &str,
+    last_run_at: DateTime<Utc>,
+    last_error: Option<String>,
+    last_result_id: Option<Uuid>,
+) -> Result<(), QueryError> {
+    let query = format!(
+        "UPDATE {}.crawl_schedules SET last_run_at = ?, last_error = ?, last_result_id = ?, locked_by = null, locked_until = null WHERE id = ? IF locked_by = ?",
+        keyspace
+    );
+    session
+        .query(query, (to_cql(last_run_at), last_error, last_result_id, id, owner))
+        .await
+        .map(|_| ())
+}
+
+/// Push the run lock `owner` holds out to `locked_until`. Returns `false` if the lock was lost.
+pub async fn extend_schedule_lock(
+    session: &Session,
+    keyspace: &str,
+    id: Uuid,
+    owner: &str,
+    locked_until: DateTime<Utc>,
+) -> Result<bool, QueryError> {
+    let query = format!(
+        "UPDATE {}.crawl_schedules SET locked_until = ? WHERE id = ? IF locked_by = ?",
+        keyspace
+    );
+    let result = session.query(query, (to_cql(locked_until), id, owner)).await?;
+    Ok(lwt_applied(result))
+}
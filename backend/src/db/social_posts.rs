@@ -0,0 +1,42 @@
+use scylla::frame::value::CqlTimestamp;
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use std::sync::Arc;
+
+use crate::social::SocialPost;
+
+/// Upsert normalized posts; a post seen again keeps its row with refreshed metrics
+pub async fn insert_social_posts(
+    session: Arc<Session>,
+    keyspace: String,
+    source: String,
+    posts: Vec<SocialPost>,
+) -> Result<(), QueryError> {
+    let query = format!(
+        "INSERT INTO {}.social_posts (platform, post_id, source, author, text, created_at, likes, comments, shares, views, media_urls, permalink, fetched_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, toTimestamp(now()))",
+        keyspace
+    );
+    for post in posts {
+        let created_at = post.created_at.map(|dt| CqlTimestamp(dt.timestamp_millis()));
+        session
+            .query(
+                query.as_str(),
+                (
+                    &post.platform,
+                    &post.post_id,
+                    &source,
+                    &post.author,
+                    &post.text,
+                    created_at,
+                    post.metrics.likes,
+                    post.metrics.comments,
+                    post.metrics.shares,
+                    post.metrics.views,
+                    &post.media_urls,
+                    &post.permalink,
+                ),
+            )
+            .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use chrono::{DateTime, TimeZone, Utc};
+use scylla::frame::response::result::CqlValue;
+use scylla::frame::value::CqlTimestamp;
+use scylla::transport::errors::QueryError;
+use scylla::{QueryResult, Session};
+use uuid::Uuid;
+
+use crate::webhooks::{WebhookDelivery, WebhookSubscription};
+
+type WebhookRow = (
+    Uuid,
+    String,
+    String,
+    Option<Vec<String>>,
+    Option<Vec<String>>,
+    Option<bool>,
+    Option<CqlTimestamp>,
+);
+
+type DeliveryRow = (
+    Uuid,
+    Uuid,
+    Option<String>,
+    Option<String>,
+    Option<i32>,
+    Option<i32>,
+    Option<String>,
+    Option<CqlTimestamp>,
+);
+
+fn from_cql(ts: CqlTimestamp) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(ts.0).single().unwrap_or_default()
+}
+
+fn row_to_webhook(row: WebhookRow) -> WebhookSubscription {
+    let (id, url, secret, keywords, domains, enabled, created_at) = row;
+    WebhookSubscription {
+        id,
+        url,
+        secret,
+        keywords: keywords.unwrap_or_default(),
+        domains: domains.unwrap_or_default(),
+        enabled: enabled.unwrap_or(true),
+        created_at: created_at.map(from_cql).unwrap_or_default(),
+    }
+}
+
+pub async fn insert_webhook(session: &Session, keyspace: &str, webhook: &WebhookSubscription) -> Result<(), QueryError> {
+    let query = format!(
+        "INSERT INTO {}.webhooks (id, url, secret, keywords, domains, enabled, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        keyspace
+    );
+    session
+        .query(
+            query,
+            (
+                webhook.id,
+                &webhook.url,
+                &webhook.secret,
+                &webhook.keywords,
+                &webhook.domains,
+                webhook.enabled,
+                CqlTimestamp(webhook.created_at.timestamp_millis()),
+            ),
+        )
+        .await
+        .map(|_| ())
+}
+
+pub async fn list_webhooks(session: &Session, keyspace: &str) -> Result<Vec<WebhookSubscription>, QueryError> {
+    let query = format!(
+        "SELECT id, url, secret, keywords, domains, enabled, created_at FROM {}.webhooks",
+        keyspace
+    );
+    let result = session.query(query, &[]).await?;
+    Ok(result
+        .rows_typed_or_empty::<WebhookRow>()
+        .filter_map(|row| row.ok())
+        .map(row_to_webhook)
+        .collect())
+}
+
+pub async fn get_webhook(session: &Session, keyspace: &str, id: Uuid) -> Result<Option<WebhookSubscription>, QueryError> {
+    let query = format!(
+        "SELECT id, url, secret, keywords, domains, enabled, created_at FROM {}.webhooks WHERE id = ?",
+        keyspace
+    );
+    let result = session.query(query, (id,)).await?;
+    Ok(result
+        .rows_typed_or_empty::<WebhookRow>()
+        .filter_map(|row| row.ok())
+        .map(row_to_webhook)
+        .next())
+}
+
+pub async fn delete_webhook(session: &Session, keyspace: &str, id: Uuid) -> Result<(), QueryError> {
+    let query = format!("DELETE FROM {}.webhooks WHERE id = ?", keyspace);
+    session.query(query, (id,)).await.map(|_| ())
+}
+
+// Alert delivery states stored in `webhook_alerts.status`
+const ALERT_PENDING: &str = "pending";
+const ALERT_DELIVERED: &str = "delivered";
+const ALERT_FAILED: &str = "failed";
+
+// The first column of an LWT result is the `[applied]` flag
+fn lwt_applied(result: QueryResult) -> bool {
+    result
+        .first_row()
+        .ok()
+        .and_then(|row| row.columns.into_iter().next().flatten())
+        .map(|value| matches!(value, CqlValue::Boolean(true)))
+        .unwrap_or(false)
+}
+
+/// Record that a subscription is being alerted about a page/keyword pair.
+/// Returns `false` when the alert was already delivered or another crawl is delivering it;
+/// alerts whose earlier delivery failed are claimed again (lightweight transactions).
+pub async fn claim_webhook_alert(
+    session: &Session,
+    keyspace: &str,
+    subscription_id: Uuid,
+    source_url: &str,
+    keyword: &str,
+) -> Result<bool, QueryError> {
+    let keyword = keyword.to_lowercase();
+    let query = format!(
+        "INSERT INTO {}.webhook_alerts (subscription_id, source_url, keyword, status, created_at) VALUES (?, ?, ?, ?, toTimestamp(now())) IF NOT EXISTS",
+        keyspace
+    );
+    let result = session
+        .query(query, (subscription_id, source_url, &keyword, ALERT_PENDING))
+        .await?;
+    if lwt_applied(result) {
+        return Ok(true);
+    }
+
+    let query = format!(
+        "UPDATE {}.webhook_alerts SET status = ?, created_at = toTimestamp(now()) WHERE subscription_id = ? AND source_url = ? AND keyword = ? IF status = ?",
+        keyspace
+    );
+    let result = session
+        .query(query, (ALERT_PENDING, subscription_id, source_url, &keyword, ALERT_FAILED))
+        .await?;
+    Ok(lwt_applied(result))
+}
+
+/// Store the outcome of a claimed alert; failed alerts are retried by the next crawl that matches them
+pub async fn finish_webhook_alert(
+    session: &Session,
+    keyspace: &str,
+    subscription_id: Uuid,
+    source_url: &str,
+    keyword: &str,
+    delivered: bool,
+) -> Result<(), QueryError> {
+    let query = format!(
+        "UPDATE {}.webhook_alerts SET status = ? WHERE subscription_id = ? AND source_url = ? AND keyword = ? IF status = ?",
+        keyspace
+    );
+    let status = if delivered { ALERT_DELIVERED } else { ALERT_FAILED };
+    session
+        .query(query, (status, subscription_id, source_url, keyword.to_lowercase(), ALERT_PENDING))
+        .await
+        .map(|_| ())
+}
+
+pub async fn insert_webhook_delivery(session: &Session, keyspace: &str, delivery: &WebhookDelivery) -> Result<(), QueryError> {
+    let query = format!(
+        "INSERT INTO {}.webhook_deliveries (subscription_id, id, source_url, keyword, attempts, status_code, error, delivered_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        keyspace
+    );
+    session
+        .query(
+            query,
+            (
+                delivery.subscription_id,
+                delivery.id,
+                &delivery.source_url,
+                &delivery.keyword,
+                delivery.attempts as i32,
+                delivery.status_code.map(|code| code as i32),
+                &delivery.error,
+                CqlTimestamp(delivery.delivered_at.timestamp_millis()),
+            ),
+        )
+        .await
+        .map(|_| ())
+}
+
+pub async fn list_webhook_deliveries(session: &Session, keyspace: &str, subscription_id: Uuid) -> Result<Vec<WebhookDelivery>, QueryError> {
+    let query = format!(
+        "SELECT subscription_id, id, source_url, keyword, attempts, status_code, error, delivered_at FROM {}.webhook_deliveries WHERE subscription_id = ?",
+        keyspace
+    );
+    let result = session.query(query, (subscription_id,)).await?;
+    Ok(result
+        .rows_typed_or_empty::<DeliveryRow>()
+        .filter_map(|row| row.ok())
+        .map(|(subscription_id, id, source_url, keyword, attempts, status_code, error, delivered_at)| WebhookDelivery {
+            id,
+            subscription_id,
+            source_url: source_url.unwrap_or_default(),
+            keyword: keyword.unwrap_or_default(),
+            attempts: attempts.unwrap_or(0) as u32,
+            status_code: status_code.map(|code| code as u16),
+            error,
+            delivered_at: delivered_at.map(from_cql).unwrap_or_default(),
+        })
+        .collect())
+}
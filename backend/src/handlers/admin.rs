@@ -0,0 +1,20 @@
+use axum::{http::StatusCode, response::IntoResponse, Json};
+
+use crate::crawler::politeness;
+use crate::models::ApiResponse;
+use crate::social::{circuit_breakers, credential_pools};
+
+/// Per-host crawl queue depth, in-flight crawls and politeness state
+pub async fn politeness_status() -> impl IntoResponse {
+    (StatusCode::OK, Json(ApiResponse::success(politeness().snapshot())))
+}
+
+/// Circuit breaker state per upstream social API host
+pub async fn social_breaker_status() -> impl IntoResponse {
+    (StatusCode::OK, Json(ApiResponse::success(circuit_breakers().snapshot())))
+}
+
+/// Requests, rejections and cooldown per upstream social API key (keys themselves are masked)
+pub async fn social_credential_usage() -> impl IntoResponse {
+    (StatusCode::OK, Json(ApiResponse::success(credential_pools().snapshot())))
+}
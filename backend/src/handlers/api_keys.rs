@@ -0,0 +1,90 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::handle_error;
+use crate::auth::{current_month, generate_api_key, hash_api_key, ApiKey, ApiKeyRequest, IssuedApiKey};
+use crate::db;
+use crate::models::{ApiError, ApiResponse};
+use crate::state::AppState;
+
+// Characters of the key kept in `key_prefix`
+const VISIBLE_KEY_CHARS: usize = 12;
+
+#[derive(Debug, Serialize)]
+pub struct ApiKeyUsage {
+    pub key_id: Uuid,
+    pub month: String,
+    pub requests: HashMap<String, i64>,
+}
+
+/// Issue a new API key. The key is only ever returned in this response.
+pub async fn create_api_key(State(app): State<AppState>, Json(body): Json<ApiKeyRequest>) -> impl IntoResponse {
+    if body.name.trim().is_empty() {
+        return handle_error::<IssuedApiKey>(ApiError::ValidationError("API key name must not be empty".to_string())).into_response();
+    }
+    if body.rate_limit_per_minute == Some(0) {
+        return handle_error::<IssuedApiKey>(ApiError::ValidationError("rate_limit_per_minute must be positive".to_string())).into_response();
+    }
+
+    let key = generate_api_key();
+    let api_key = ApiKey {
+        id: Uuid::new_v4(),
+        name: body.name,
+        key_prefix: key.chars().take(VISIBLE_KEY_CHARS).collect(),
+        rate_limit_per_minute: body.rate_limit_per_minute.unwrap_or_else(|| app.api_keys.default_rate_limit()),
+        crawl_monthly_quota: body.crawl_monthly_quota,
+        social_monthly_quota: body.social_monthly_quota,
+        revoked: false,
+        created_at: Utc::now(),
+    };
+
+    let state = &app.cassandra;
+    match db::insert_api_key(&state.session, &state.keyspace, &hash_api_key(&key), &api_key).await {
+        Ok(()) => (StatusCode::CREATED, Json(ApiResponse::success(IssuedApiKey { key, api_key }))).into_response(),
+        Err(e) => handle_error::<IssuedApiKey>(ApiError::DatabaseError(e.to_string())).into_response(),
+    }
+}
+
+pub async fn list_api_keys(State(app): State<AppState>) -> impl IntoResponse {
+    let state = &app.cassandra;
+    match db::list_api_keys(&state.session, &state.keyspace).await {
+        Ok(keys) => (StatusCode::OK, Json(ApiResponse::success(keys))).into_response(),
+        Err(e) => handle_error::<Vec<ApiKey>>(ApiError::DatabaseError(e.to_string())).into_response(),
+    }
+}
+
+pub async fn revoke_api_key(State(app): State<AppState>, Path(id): Path<Uuid>) -> impl IntoResponse {
+    let state = &app.cassandra;
+    match db::revoke_api_key(&state.session, &state.keyspace, id).await {
+        Ok(Some(api_key)) => {
+            app.api_keys.forget(id);
+            (StatusCode::OK, Json(ApiResponse::success(api_key))).into_response()
+        }
+        Ok(None) => handle_error::<ApiKey>(ApiError::NotFound(format!("API key {} not found", id))).into_response(),
+        Err(e) => handle_error::<ApiKey>(ApiError::DatabaseError(e.to_string())).into_response(),
+    }
+}
+
+/// Requests per quota scope for the current month
+pub async fn get_api_key_usage(State(app): State<AppState>, Path(id): Path<Uuid>) -> impl IntoResponse {
+    let state = &app.cassandra;
+    let month = current_month();
+    match db::get_api_key_usage(&state.session, &state.keyspace, id, &month).await {
+        Ok(rows) => {
+            let usage = ApiKeyUsage {
+                key_id: id,
+                month,
+                requests: rows.into_iter().collect(),
+            };
+            (StatusCode::OK, Json(ApiResponse::success(usage))).into_response()
+        }
+        Err(e) => handle_error::<ApiKeyUsage>(ApiError::DatabaseError(e.to_string())).into_response(),
+    }
+}
@@ -1,53 +1,205 @@
 use axum::{
-    extract::{Json, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Json, Path, State},
+    http::{header::RETRY_AFTER, StatusCode},
+    response::{IntoResponse, Response},
 };
 use serde_json::json;
+use tokio::time::Duration;
 use uuid::Uuid;
+use crate::config::CrawlLimits;
 use crate::db::CassandraState;
+use crate::models::{ApiError, ApiResponse};
+use crate::state::AppState;
+use super::handle_error;
 
-use crate::crawler::{CrawlRequest, CrawlerError};
+use crate::db::{load_fetch_states, upsert_fetch_state};
+use crate::crawler::{CrawlRequest, CrawlResult, CrawlerError, PoolSaturated, PreviousFetchStates};
+
+// Load what previous crawls saw for the requested hosts, for `only_changed` recrawls
+async fn load_previous_fetch_states(state: &CassandraState, request: &CrawlRequest) -> PreviousFetchStates {
+    let mut previous = PreviousFetchStates::new();
+    if !request.only_changed.unwrap_or(false) {
+        return previous;
+    }
+
+    let hosts = crate::crawler::seed_hosts(request).unwrap_or_default();
+    for host in hosts {
+        match load_fetch_states(&state.session, &state.keyspace, &host).await {
+            Ok(states) => previous.extend(states.into_iter().map(|s| (s.url.clone(), s))),
+            Err(e) => eprintln!("Failed to load fetch state for {}: {}", host, e),
+        }
+    }
+    previous
+}
+
+// Persist per-URL fetch state in the background so the next crawl can skip unchanged pages
+fn persist_fetch_states(state: &CassandraState, result: &CrawlResult) {
+    let fetch_states: Vec<_> = result
+        .results
+        .iter()
+        .flat_map(|domain| domain.fetch_states.iter().cloned())
+        .collect();
+    if fetch_states.is_empty() {
+        return;
+    }
+
+    let session = state.session.clone();
+    let keyspace = state.keyspace.clone();
+    tokio::spawn(async move {
+        for fetch_state in &fetch_states {
+            if let Err(e) = upsert_fetch_state(&session, &keyspace, fetch_state).await {
+                eprintln!("[Cassandra] Fetch state upsert failed for {}: {}", fetch_state.url, e);
+            }
+        }
+    });
+}
+
+/// Errors from running a crawl end to end: crawling, then persisting the result
+#[derive(Debug, thiserror::Error)]
+pub enum CrawlRunError {
+    #[error(transparent)]
+    Crawler(#[from] CrawlerError),
+
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+
+    #[error("Failed to persist crawl result: {0}")]
+    Persistence(String),
+}
+
+/// Check a crawl request against the server-side limits and fill in defaults for
+/// anything left unbounded, so no request can ask for more than the server allows
+pub fn apply_crawl_limits(mut request: CrawlRequest, limits: &CrawlLimits) -> Result<CrawlRequest, ApiError> {
+    let url_count = crate::crawler::seed_hosts(&request)
+        .map_err(|e| ApiError::ValidationError(e.to_string()))?
+        .len();
+    if url_count > limits.max_urls_per_request {
+        return Err(ApiError::ValidationError(format!(
+            "At most {} URLs may be crawled per request, got {}",
+            limits.max_urls_per_request, url_count
+        )));
+    }
+
+    match request.max_pages {
+        Some(max_pages) if max_pages > limits.max_pages => {
+            return Err(ApiError::ValidationError(format!(
+                "max_pages must be at most {}",
+                limits.max_pages
+            )));
+        }
+        Some(_) => {}
+        None => request.max_pages = Some(limits.max_pages),
+    }
+
+    match request.max_depth {
+        Some(max_depth) if max_depth > limits.max_depth => {
+            return Err(ApiError::ValidationError(format!(
+                "max_depth must be at most {}",
+                limits.max_depth
+            )));
+        }
+        Some(_) => {}
+        None => request.max_depth = Some(limits.max_depth),
+    }
+
+    match request.max_time_seconds {
+        Some(max_time) if max_time > limits.max_time_seconds => {
+            return Err(ApiError::ValidationError(format!(
+                "max_time_seconds must be at most {}",
+                limits.max_time_seconds
+            )));
+        }
+        Some(_) => {}
+        None => request.max_time_seconds = Some(limits.max_time_seconds),
+    }
+
+    Ok(request)
+}
+
+/// Run a crawl and store its result in `crawl_results`, returning the stored result id.
+/// Shared by the `/crawl` endpoint and scheduled crawls; the server's `max_time_seconds`
+/// is a hard deadline for the whole crawl, after which partial results are stored as `truncated`.
+pub async fn execute_crawl(app: &AppState, request: &CrawlRequest) -> Result<(Uuid, CrawlResult), CrawlRunError> {
+    let state = &app.cassandra;
+    let previous = load_previous_fetch_states(state, request).await;
+    let time_budget = Duration::from_secs(app.crawl_limits.max_time_seconds);
+    let result = crate::crawler::crawl_website(request, &previous, &app.http, time_budget).await?;
+    persist_fetch_states(state, &result);
+
+    // Serialize and store the result directly into Cassandra
+    let payload = serde_json::to_string(&result).map_err(|e| CrawlRunError::Serialization(e.to_string()))?;
+    let id = Uuid::new_v4();
+    let query = format!(
+        "INSERT INTO {}.crawl_results (id, payload, created_at) VALUES (?, ?, toTimestamp(now()))",
+        state.keyspace
+    );
+    state
+        .session
+        .query(query, (id, payload))
+        .await
+        .map_err(|e| CrawlRunError::Persistence(e.to_string()))?;
+
+    // Alert webhook subscribers about the matches in the background
+    let matches = result
+        .results
+        .iter()
+        .flat_map(|domain| domain.matches.iter().cloned())
+        .collect();
+    tokio::spawn(crate::webhooks::dispatch_matches(state.clone(), app.http.clone(), id, matches));
+
+    Ok((id, result))
+}
+
+// Helper function to turn a client away from a saturated crawler pool with 429 and Retry-After
+fn saturated_response(saturated: &PoolSaturated) -> Response {
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(RETRY_AFTER, saturated.retry_after.as_secs().max(1).to_string())],
+        Json(ApiResponse::<CrawlResult>::error("Crawler pool is saturated, retry later")),
+    )
+        .into_response()
+}
 
 pub async fn crawl_website(
-    State(state): State<CassandraState>,
+    State(state): State<AppState>,
     Json(request): Json<CrawlRequest>,
 ) -> impl IntoResponse {
-    match crate::crawler::crawl_website(&request).await {
-        Ok(result) => {
-            // Serialize and store the result directly into Cassandra
-            match serde_json::to_string(&result) {
-                Ok(payload) => {
-                    let id = Uuid::new_v4();
-                    let query = format!(
-                        "INSERT INTO {}.crawl_results (id, payload, created_at) VALUES (?, ?, toTimestamp(now()))",
-                        state.keyspace
-                    );
-                    if let Err(e) = state.session.query(query, (id, payload)).await {
-                        eprintln!("Failed to insert crawl result into Cassandra: {}", e);
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(json!({"error": "Failed to persist crawl result"})),
-                        )
-                            .into_response();
-                    }
-                    (StatusCode::OK, Json(result)).into_response()
-                }
-                Err(e) => (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(json!({"error": format!("Serialization error: {}", e)})),
-                )
-                    .into_response(),
-            }
-        },
-        Err(err) => {
+    let request = match apply_crawl_limits(request, &state.crawl_limits) {
+        Ok(request) => request,
+        Err(err) => return handle_error::<CrawlResult>(err).into_response(),
+    };
+
+    // Wait for a slot in the crawler pool, or turn the client away when the queue is full too
+    let _permit = match state.crawl_admission.admit().await {
+        Ok(permit) => permit,
+        Err(saturated) => return saturated_response(&saturated),
+    };
+
+    match execute_crawl(&state, &request).await {
+        Ok((_, result)) => (StatusCode::OK, Json(result)).into_response(),
+        Err(CrawlRunError::Serialization(e)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("Serialization error: {}", e)})),
+        )
+            .into_response(),
+        Err(CrawlRunError::Persistence(e)) => {
+            eprintln!("Failed to insert crawl result into Cassandra: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": "Failed to persist crawl result"})),
+            )
+                .into_response()
+        }
+        Err(CrawlRunError::Crawler(err)) => {
             let (status, error_message) = match &err {
                 CrawlerError::RequestError(e) => (StatusCode::BAD_REQUEST, format!("Request error: {}", e)),
                 CrawlerError::UrlError(e) => (StatusCode::BAD_REQUEST, format!("Invalid URL: {}", e)),
                 CrawlerError::SelectorError(e) => (StatusCode::BAD_REQUEST, format!("Selector error: {}", e)),
-                CrawlerError::TimeoutError => (StatusCode::OK, "Crawling exceeded the time limit".to_string()),
+                CrawlerError::TimeoutError => (StatusCode::GATEWAY_TIMEOUT, "Crawling exceeded the time limit".to_string()),
                 CrawlerError::DateParsingError(e) => (StatusCode::BAD_REQUEST, format!("Date parsing error: {}", e)),
                 CrawlerError::SpiderError(e) => (StatusCode::BAD_REQUEST, format!("Spider error: {}", e)),
+                CrawlerError::PatternError(e) => (StatusCode::BAD_REQUEST, format!("Pattern error: {}", e)),
+                CrawlerError::BlockedUrl(e) => (StatusCode::FORBIDDEN, format!("Blocked URL: {}", e)),
                 CrawlerError::Other(e) => (StatusCode::BAD_REQUEST, format!("Other error: {}", e)),
             };
             
@@ -60,4 +212,45 @@ pub async fn crawl_website(
                 .into_response()
         }
     }
-}
\ No newline at end of file
+}
+
+/// A stored crawl result, as returned by the crawl that produced it
+pub async fn get_crawl_result(State(state): State<CassandraState>, Path(id): Path<Uuid>) -> impl IntoResponse {
+    let payload = match crate::db::get_crawl_result(&state.session, &state.keyspace, id).await {
+        Ok(Some(payload)) => payload,
+        Ok(None) => return handle_error::<CrawlResult>(ApiError::NotFound(format!("Crawl result {} not found", id))).into_response(),
+        Err(e) => return handle_error::<CrawlResult>(ApiError::DatabaseError(e.to_string())).into_response(),
+    };
+
+    match serde_json::from_str::<CrawlResult>(&payload) {
+        Ok(result) => (StatusCode::OK, Json(ApiResponse::success(result))).into_response(),
+        Err(e) => handle_error::<CrawlResult>(ApiError::InternalServerError(format!("Unreadable crawl result: {}", e))).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn saturated_pool_answers_429_with_retry_after() {
+        let response = saturated_response(&PoolSaturated {
+            retry_after: Duration::from_secs(30),
+        });
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get(RETRY_AFTER).unwrap(), "30");
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["success"], false);
+        assert_eq!(body["message"], "Crawler pool is saturated, retry later");
+    }
+
+    #[test]
+    fn retry_after_is_at_least_one_second() {
+        let response = saturated_response(&PoolSaturated {
+            retry_after: Duration::from_millis(200),
+        });
+        assert_eq!(response.headers().get(RETRY_AFTER).unwrap(), "1");
+    }
+}
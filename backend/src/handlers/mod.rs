@@ -6,8 +6,33 @@ use axum::{
 };
 use crate::models::{ApiError, ApiResponse, CreateUserRequest, UpdateUserRequest, User};
 
+mod admin;
+pub use admin::{politeness_status, social_breaker_status, social_credential_usage};
+mod api_keys;
+pub use api_keys::{
+    create_api_key,
+    list_api_keys,
+    revoke_api_key,
+    get_api_key_usage,
+};
 mod crawler;
-pub use crawler::crawl_website;
+pub use crawler::{crawl_website, execute_crawl, get_crawl_result};
+mod schedules;
+pub use schedules::{
+    create_schedule,
+    list_schedules,
+    get_schedule,
+    update_schedule,
+    delete_schedule,
+};
+mod webhooks;
+pub use webhooks::{
+    create_webhook,
+    list_webhooks,
+    get_webhook,
+    delete_webhook,
+    list_webhook_deliveries,
+};
 mod social;
 pub use social::{
     proxy_tikhub_twitter,
@@ -16,6 +41,8 @@ pub use social::{
     proxy_rapidapi_twitter_v24,
     proxy_rapidapi_generic,
     proxy_tikhub_generic,
+    proxy_provider,
+    social_search,
 };
 
 pub async fn health_check() -> impl IntoResponse {
@@ -62,6 +89,7 @@ fn handle_error<T>(err: ApiError) -> (StatusCode, Json<ApiResponse<T>>) {
     let status = match &err {
         ApiError::NotFound(_) => StatusCode::NOT_FOUND,
         ApiError::ValidationError(_) => StatusCode::BAD_REQUEST,
+        ApiError::Conflict(_) => StatusCode::CONFLICT,
         ApiError::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         ApiError::InternalServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
     };
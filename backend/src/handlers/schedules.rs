@@ -0,0 +1,133 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::Utc;
+use uuid::Uuid;
+
+use super::crawler::apply_crawl_limits;
+use super::handle_error;
+use crate::config::CrawlLimits;
+use crate::db::{self, CassandraState};
+use crate::models::{ApiError, ApiResponse};
+use crate::scheduler::{next_run_after, Schedule, ScheduleRequest};
+use crate::state::AppState;
+
+// How often an edit is retried after losing the `next_run_at` compare-and-set to a claim
+const UPDATE_ATTEMPTS: usize = 3;
+
+// Helper function to validate a schedule body before it is stored; the stored crawl request
+// has the server-side crawl limits applied just like an ad-hoc crawl
+fn validate_schedule_request(mut body: ScheduleRequest, limits: &CrawlLimits) -> Result<ScheduleRequest, ApiError> {
+    body.request = apply_crawl_limits(body.request, limits)?;
+    next_run_after(body.cron.as_deref(), body.interval_seconds, Utc::now()).map_err(ApiError::ValidationError)?;
+    Ok(body)
+}
+
+async fn load_schedule(state: &CassandraState, id: Uuid) -> Result<Schedule, ApiError> {
+    db::get_schedule(&state.session, &state.keyspace, id)
+        .await
+        .map_err(|e| ApiError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| ApiError::NotFound(format!("Schedule {} not found", id)))
+}
+
+pub async fn create_schedule(
+    State(app): State<AppState>,
+    Json(body): Json<ScheduleRequest>,
+) -> impl IntoResponse {
+    let state = &app.cassandra;
+    let body = match validate_schedule_request(body, &app.crawl_limits) {
+        Ok(body) => body,
+        Err(err) => return handle_error::<Schedule>(err).into_response(),
+    };
+
+    let now = Utc::now();
+    let schedule = Schedule {
+        id: Uuid::new_v4(),
+        name: body.name,
+        next_run_at: next_run_after(body.cron.as_deref(), body.interval_seconds, now).unwrap_or(now),
+        request: body.request,
+        cron: body.cron,
+        interval_seconds: body.interval_seconds,
+        enabled: body.enabled.unwrap_or(true),
+        last_run_at: None,
+        last_error: None,
+        last_result_id: None,
+        created_at: now,
+        running_until: None,
+    };
+
+    match db::insert_schedule(&state.session, &state.keyspace, &schedule).await {
+        Ok(true) => (StatusCode::CREATED, Json(ApiResponse::success(schedule))).into_response(),
+        Ok(false) => handle_error::<Schedule>(ApiError::Conflict(format!("Schedule {} already exists", schedule.id))).into_response(),
+        Err(e) => handle_error::<Schedule>(ApiError::DatabaseError(e.to_string())).into_response(),
+    }
+}
+
+pub async fn list_schedules(State(state): State<CassandraState>) -> impl IntoResponse {
+    match db::list_schedules(&state.session, &state.keyspace).await {
+        Ok(schedules) => (StatusCode::OK, Json(ApiResponse::success(schedules))).into_response(),
+        Err(e) => handle_error::<Vec<Schedule>>(ApiError::DatabaseError(e.to_string())).into_response(),
+    }
+}
+
+pub async fn get_schedule(State(state): State<CassandraState>, Path(id): Path<Uuid>) -> impl IntoResponse {
+    match load_schedule(&state, id).await {
+        Ok(schedule) => (StatusCode::OK, Json(ApiResponse::success(schedule))).into_response(),
+        Err(err) => handle_error::<Schedule>(err).into_response(),
+    }
+}
+
+pub async fn update_schedule(
+    State(app): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<ScheduleRequest>,
+) -> impl IntoResponse {
+    let state = &app.cassandra;
+    let body = match validate_schedule_request(body, &app.crawl_limits) {
+        Ok(body) => body,
+        Err(err) => return handle_error::<Schedule>(err).into_response(),
+    };
+
+    // `next_run_at` is only ever written conditionally, so an edit racing a replica that claims
+    // a run reloads the schedule and tries again instead of overwriting the claim
+    for _ in 0..UPDATE_ATTEMPTS {
+        let existing = match load_schedule(state, id).await {
+            Ok(schedule) => schedule,
+            Err(err) => return handle_error::<Schedule>(err).into_response(),
+        };
+        let expected_next_run_at = existing.next_run_at;
+
+        // A new cadence takes effect from now; run history is kept
+        let now = Utc::now();
+        let schedule = Schedule {
+            name: body.name.clone(),
+            next_run_at: next_run_after(body.cron.as_deref(), body.interval_seconds, now).unwrap_or(now),
+            request: body.request.clone(),
+            cron: body.cron.clone(),
+            interval_seconds: body.interval_seconds,
+            enabled: body.enabled.unwrap_or(existing.enabled),
+            ..existing
+        };
+
+        match db::update_schedule(&state.session, &state.keyspace, &schedule, expected_next_run_at).await {
+            Ok(true) => return (StatusCode::OK, Json(ApiResponse::success(schedule))).into_response(),
+            Ok(false) => continue,
+            Err(e) => return handle_error::<Schedule>(ApiError::DatabaseError(e.to_string())).into_response(),
+        }
+    }
+
+    handle_error::<Schedule>(ApiError::Conflict(format!("Schedule {} is being updated concurrently; retry", id))).into_response()
+}
+
+pub async fn delete_schedule(State(state): State<CassandraState>, Path(id): Path<Uuid>) -> impl IntoResponse {
+    if let Err(err) = load_schedule(&state, id).await {
+        return handle_error::<Schedule>(err).into_response();
+    }
+
+    match db::delete_schedule(&state.session, &state.keyspace, id).await {
+        Ok(()) => (StatusCode::OK, Json(ApiResponse::success(id))).into_response(),
+        Err(e) => handle_error::<Uuid>(ApiError::DatabaseError(e.to_string())).into_response(),
+    }
+}
@@ -1,450 +1,461 @@
 use axum::{
-    extract::{Json, State},
-    http::StatusCode,
+    body::Body,
+    extract::{Json, Path, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
 };
+use chrono::{DateTime, Utc};
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use serde_json::{json, Value};
-use crate::db::{CassandraState, insert_social_result};
-
-const TIKHUB_TWITTER_BASE: &str = "https://api.tikhub.io/api/v1/twitter/web/";
-const TIKHUB_TIKTOK_BASE: &str = "https://api.tikhub.io/api/v1/tiktok/web/";
-const RAPIDAPI_INSTAGRAM_HOST: &str = "instagram-scraper-api2.p.rapidapi.com";
-const RAPIDAPI_TWITTER_V24_HOST: &str = "twitter-v24.p.rapidapi.com";
+use std::sync::Arc;
+use tokio::task::JoinSet;
+use tokio::time::Duration;
+use url::Url;
+use crate::db::{insert_social_posts, insert_social_result, CassandraState, NewSocialResult};
+use crate::outbound::check_url;
+use crate::social::{
+    self, ProviderError, ProxyRequest, RapidApiProvider, SocialPost, SocialProvider, TikHubProvider, UpstreamBody,
+    UpstreamResponse, SEARCH_PLATFORMS,
+};
+use crate::state::AppState;
 
-#[derive(Deserialize)]
-pub struct ProxyRequest {
-    pub path: String,
-    #[serde(default)]
-    pub params: Option<Value>,
-    #[serde(default)]
-    pub method: Option<String>,
+// Helper function for rejected upstream targets
+fn forbidden_target(message: &str) -> Response {
+    (StatusCode::FORBIDDEN, Json(json!({"error": message}))).into_response()
 }
 
-#[derive(Serialize)]
-pub struct ProxyResponse {
-    pub status: u16,
-    pub data: Value,
+// Helper function to vet an upstream URL built from user input: it must stay on `expected_host`
+// and pass the outbound guard. The shared clients keep redirects and DNS on the same guard.
+async fn check_upstream_target(url: &str, expected_host: &str) -> Result<(), String> {
+    let parsed = Url::parse(url).map_err(|e| format!("Invalid upstream URL: {}", e))?;
+    let same_host = parsed.host_str().is_some_and(|host| host.eq_ignore_ascii_case(expected_host));
+    if !same_host || !parsed.username().is_empty() || parsed.port().is_some() {
+        return Err(format!("Upstream host {} is not allowed", expected_host));
+    }
+    check_url(&parsed).await.map_err(|e| e.to_string())
 }
 
-fn params_to_query(params: &Option<Value>) -> Vec<(String, String)> {
-    let mut query = Vec::new();
-    if let Some(Value::Object(map)) = params {
-        for (k, v) in map.iter() {
-            let val = match v {
-                Value::String(s) => s.clone(),
-                _ => v.to_string(),
-            };
-            query.push((k.clone(), val));
-        }
+// Helper function to find why a proxy call may not be sent, if it may not: the provider's
+// service or host must allow the path and method in `SOCIAL_PROXY_ALLOWLIST`, and the upstream
+// URL must stay under the provider's base URL on its own host
+async fn proxy_target_error(app: &AppState, provider: &dyn SocialProvider, request: &ProxyRequest) -> Result<(), String> {
+    let target = provider.target();
+    let method = request.method();
+    let (kind, allowed) = match provider.credential_pool() {
+        "tikhub" => ("TikHub", app.social_allowlist.allows_tikhub(&target, &request.path, &method)),
+        _ => ("RapidAPI", app.social_allowlist.allows_rapidapi(&target, &request.path, &method)),
+    };
+    if !allowed {
+        return Err(format!(
+            "{} {} {}/{} is not in the proxy allowlist",
+            kind,
+            method,
+            target.trim_matches('/'),
+            request.path.trim_start_matches('/')
+        ));
     }
-    query
+
+    let url = social::upstream_url(provider, &request.path)
+        .ok_or_else(|| format!("Upstream path {} is not allowed", request.path))?;
+    let host = Url::parse(&provider.base_url())
+        .ok()
+        .and_then(|base| base.host_str().map(str::to_string))
+        .unwrap_or_default();
+    check_upstream_target(&url, &host).await
 }
 
-// For TikHub Twitter, ensure `keyword` is used (map `q` -> `keyword`) and default `search_type=Top`.
-fn tikhub_twitter_query(params: &Option<Value>) -> Vec<(String, String)> {
-    let mut query: Vec<(String, String)> = Vec::new();
-    let mut has_search_type = false;
-
-    if let Some(Value::Object(map)) = params {
-        // Prefer `keyword`, fallback to `q`
-        if let Some(v) = map.get("keyword") {
-            let val = match v { Value::String(s) => s.clone(), _ => v.to_string() };
-            query.push(("keyword".to_string(), val));
-        } else if let Some(v) = map.get("q") {
-            let val = match v { Value::String(s) => s.clone(), _ => v.to_string() };
-            query.push(("keyword".to_string(), val));
-        }
+// Helper function to vet a proxy call before it is sent, answering 403 when it is refused
+async fn check_proxy_target(app: &AppState, provider: &dyn SocialProvider, request: &ProxyRequest) -> Result<(), Response> {
+    proxy_target_error(app, provider, request).await.map_err(|message| forbidden_target(&message))
+}
 
-        // Carry `search_type` if provided
-        if let Some(v) = map.get("search_type") {
-            let val = match v { Value::String(s) => s.clone(), _ => v.to_string() };
-            query.push(("search_type".to_string(), val));
-            has_search_type = true;
-        }
+// Helper function: `Cache-Control: no-cache` (or `no-store`) skips the response cache
+fn bypasses_cache(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CACHE_CONTROL)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_ascii_lowercase())
+        .is_some_and(|v| v.contains("no-cache") || v.contains("no-store"))
+}
 
-        // Include remaining params except `q`, `keyword`, `search_type`
-        for (k, v) in map.iter() {
-            if k == "q" || k == "keyword" || k == "search_type" { continue; }
-            let val = match v { Value::String(s) => s.clone(), _ => v.to_string() };
-            query.push((k.clone(), val));
-        }
+// Helper function to build a proxy response tagged with `x-cache: hit|miss`, passing on the
+// upstream's `Retry-After` when it rate limited us
+fn proxy_response(status: StatusCode, cached: bool, retry_after: Option<Duration>, body: Value) -> Response {
+    let x_cache = if cached { "hit" } else { "miss" };
+    let mut resp = (status, [("x-cache", x_cache)], Json(body)).into_response();
+    if let Some(retry_after) = retry_after {
+        resp.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after.as_secs().max(1)));
     }
+    resp
+}
 
-    // Default search_type
-    if !has_search_type {
-        query.push(("search_type".to_string(), "Top".to_string()));
+// Helper function to map an upstream status to ours. Our own credentials being rejected and
+// upstream errors are the upstream's failure (502); rate limits and request errors pass through.
+fn upstream_status_code(status: u16) -> StatusCode {
+    match status {
+        200..=299 => StatusCode::OK,
+        401 | 403 => StatusCode::BAD_GATEWAY,
+        400..=499 => StatusCode::from_u16(status).unwrap_or(StatusCode::BAD_GATEWAY),
+        _ => StatusCode::BAD_GATEWAY,
     }
-
-    // If no keyword provided, we still return any extra params; TikHub may error accordingly.
-    query
 }
 
-// For TikHub TikTok (web), ensure `keyword` is used (map `q` -> `keyword`) and default `count=20`, `offset=0`.
-fn tikhub_tiktok_query(params: &Option<Value>) -> Vec<(String, String)> {
-    let mut query: Vec<(String, String)> = Vec::new();
-    let mut has_count = false;
-    let mut has_offset = false;
-
-    if let Some(Value::Object(map)) = params {
-        // Prefer `keyword`, fallback to `q`
-        if let Some(v) = map.get("keyword") {
-            let val = match v { Value::String(s) => s.clone(), _ => v.to_string() };
-            query.push(("keyword".to_string(), val));
-        } else if let Some(v) = map.get("q") {
-            let val = match v { Value::String(s) => s.clone(), _ => v.to_string() };
-            query.push(("keyword".to_string(), val));
-        }
-
-        // Carry `count` if provided
-        if let Some(v) = map.get("count") {
-            let val = match v { Value::String(s) => s.clone(), _ => v.to_string() };
-            query.push(("count".to_string(), val));
-            has_count = true;
+// Helper function for calls that never got a usable upstream response
+fn provider_error(e: ProviderError) -> Response {
+    let (status, retry_after) = match &e {
+        ProviderError::MissingCredential(_) => (StatusCode::INTERNAL_SERVER_ERROR, None),
+        ProviderError::Request(_) => (StatusCode::BAD_GATEWAY, None),
+        ProviderError::Timeout => (StatusCode::GATEWAY_TIMEOUT, None),
+        ProviderError::InvalidPath(_) => (StatusCode::FORBIDDEN, None),
+        ProviderError::RateLimited(wait) => (StatusCode::TOO_MANY_REQUESTS, Some(*wait)),
+        ProviderError::CredentialsCoolingDown { retry_in, .. } => (StatusCode::SERVICE_UNAVAILABLE, Some(*retry_in)),
+        ProviderError::CircuitOpen { provider, retry_in, .. } => {
+            let body = json!({"error": e.to_string(), "provider": provider});
+            return proxy_response(StatusCode::SERVICE_UNAVAILABLE, false, Some(*retry_in), body);
         }
+    };
+    proxy_response(status, false, retry_after, json!({"error": e.to_string()}))
+}
 
-        // Carry `offset` if provided
-        if let Some(v) = map.get("offset") {
-            let val = match v { Value::String(s) => s.clone(), _ => v.to_string() };
-            query.push(("offset".to_string(), val));
-            has_offset = true;
-        }
+// Helper function to take the response value out of an upstream response, storing the raw
+// payload in the background unless it was served from the cache
+fn store_upstream(app: &AppState, source: String, body: &ProxyRequest, upstream: UpstreamResponse) -> Value {
+    let (data, payload_opt) = body_value(upstream.body);
+    let Some(payload_json) = payload_opt.filter(|_| !upstream.cached) else {
+        return data;
+    };
 
-        // Include remaining params except these mapped ones
-        for (k, v) in map.iter() {
-            if k == "q" || k == "keyword" || k == "count" || k == "offset" { continue; }
-            let val = match v { Value::String(s) => s.clone(), _ => v.to_string() };
-            query.push((k.clone(), val));
+    let session = app.cassandra.session.clone();
+    let keyspace = app.cassandra.keyspace.clone();
+    let result = NewSocialResult {
+        source,
+        request_path: body.path.clone(),
+        params_json: body.params.as_ref().and_then(|v| serde_json::to_string(v).ok()),
+        payload_json,
+        status: upstream.status as i32,
+        cache_key: upstream.cache_key,
+    };
+    tokio::spawn(async move {
+        let source_for_log = result.source.clone();
+        match insert_social_result(session, keyspace, result).await {
+            Ok(()) => eprintln!("[Cassandra] Inserted social_result: {}", source_for_log),
+            Err(e) => eprintln!("[Cassandra] Insert failed: {}", e),
         }
-    }
-
-    // Defaults per curl example
-    if !has_count { query.push(("count".to_string(), "20".to_string())); }
-    if !has_offset { query.push(("offset".to_string(), "0".to_string())); }
-
-    // If no keyword provided, we still return any extra params; TikHub may error accordingly.
-    query
+    });
+    data
 }
 
-async fn execute_request(client: &Client, req: reqwest::RequestBuilder) -> Response {
-    match req.send().await {
-        Ok(resp) => {
-            let status_u16 = resp.status().as_u16();
-            let ct = resp
-                .headers()
-                .get(reqwest::header::CONTENT_TYPE)
-                .and_then(|x| x.to_str().ok())
-                .unwrap_or("")
-                .to_string();
-
-            if ct.contains("application/json") {
-                match resp.json::<Value>().await {
-                    Ok(data) => (StatusCode::OK, Json(json!({"status": status_u16, "data": data}))).into_response(),
-                    Err(_) => (StatusCode::OK, Json(json!({"status": status_u16, "data": Value::Null}))).into_response(),
-                }
-            } else {
-                match resp.text().await {
-                    Ok(text) => (StatusCode::OK, Json(json!({"status": status_u16, "data_text": text}))).into_response(),
-                    Err(_) => (StatusCode::OK, Json(json!({"status": status_u16, "data_text": ""}))).into_response(),
-                }
-            }
-        }
-        Err(err) => (StatusCode::BAD_REQUEST, Json(json!({"error": format!("Request failed: {}", err)}))).into_response(),
+// Helper function to store normalized posts in the background
+fn store_posts(app: &AppState, source: String, posts: Vec<SocialPost>) {
+    if posts.is_empty() {
+        return;
     }
+    let session = app.cassandra.session.clone();
+    let keyspace = app.cassandra.keyspace.clone();
+    tokio::spawn(async move {
+        let count = posts.len();
+        match insert_social_posts(session, keyspace, source.clone(), posts).await {
+            Ok(()) => eprintln!("[Cassandra] Inserted {} social_posts: {}", count, source),
+            Err(e) => eprintln!("[Cassandra] Insert social_posts failed: {}", e),
+        }
+    });
 }
 
-async fn execute_request_capture(client: &Client, req: reqwest::RequestBuilder) -> (Response, Option<String>) {
-    match req.send().await {
-        Ok(resp) => {
-            let status_u16 = resp.status().as_u16();
-            let ct = resp
-                .headers()
-                .get(reqwest::header::CONTENT_TYPE)
-                .and_then(|x| x.to_str().ok())
-                .unwrap_or("")
-                .to_string();
-
-            if ct.contains("application/json") {
-                match resp.json::<Value>().await {
-                    Ok(data) => {
-                        let payload_str = serde_json::to_string(&data).ok();
-                        ((StatusCode::OK, Json(json!({"status": status_u16, "data": data}))).into_response(), payload_str)
-                    }
-                    Err(_) => ((StatusCode::OK, Json(json!({"status": status_u16, "data": Value::Null}))).into_response(), None),
-                }
-            } else {
-                match resp.text().await {
-                    Ok(text) => ((StatusCode::OK, Json(json!({"status": status_u16, "data_text": text}))).into_response(), Some(text)),
-                    Err(_) => ((StatusCode::OK, Json(json!({"status": status_u16, "data_text": ""}))).into_response(), None),
-                }
-            }
+// Helper function to split an upstream body into its response value and the payload to store
+fn body_value(body: UpstreamBody) -> (Value, Option<String>) {
+    match body {
+        UpstreamBody::Json(Some(data)) => {
+            let payload_str = serde_json::to_string(&data).ok();
+            (data, payload_str)
         }
-        Err(err) => ((StatusCode::BAD_REQUEST, Json(json!({"error": format!("Request failed: {}", err)}))).into_response(), None),
+        UpstreamBody::Json(None) => (Value::Null, None),
+        UpstreamBody::Text(Some(text)) => (Value::String(text.clone()), Some(text)),
+        UpstreamBody::Text(None) => (Value::String(String::new()), None),
     }
 }
 
-#[derive(Deserialize)]
-pub struct TikHubGenericRequest {
-    pub service: String, // e.g., "twitter/web" or "tiktok/app/v3"
-    pub path: String,
-    #[serde(default)]
-    pub params: Option<Value>,
-    #[serde(default)]
-    pub method: Option<String>,
-}
+// Proxy one request through a provider, store the raw payload in the background and return
+// `{"status", "data"}` (JSON upstream) or `{"status", "data_text"}` (anything else). Providers
+// with a post layout also return the mapped posts under `normalized` and the `next_cursor`,
+// and store the posts in `social_posts`. Requests with `max_items`/`max_pages` are paginated.
+// Successful responses are cached; `x-cache` says whether this one was. `raw` requests are
+// streamed back untouched instead.
+async fn proxy_through(
+    app: &AppState,
+    provider: &dyn SocialProvider,
+    client: &Client,
+    body: &ProxyRequest,
+    bypass_cache: bool,
+) -> Response {
+    if body.raw {
+        return proxy_raw(app, provider, client, body).await;
+    }
+    if body.paginates() && provider.post_layout().is_some() {
+        return proxy_pages(app, provider, client, body, bypass_cache).await;
+    }
 
-pub async fn proxy_tikhub_generic(State(state): State<CassandraState>, Json(body): Json<TikHubGenericRequest>) -> impl IntoResponse {
-    let token = match std::env::var("TIKHUB_TOKEN") {
-        Ok(v) => v,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": "Missing TIKHUB_TOKEN in environment"})),
-            )
-                .into_response();
-        }
+    let mut upstream = match social::send_cached(&app.social_cache, provider, client, body, bypass_cache).await {
+        Ok(upstream) => upstream,
+        Err(e) => return provider_error(e),
     };
 
-    let service = body.service.trim_matches('/');
-    let url = format!("https://api.tikhub.io/api/v1/{}/{}", service, body.path.trim_start_matches('/'));
-    let client = Client::new();
-
-    let method = body.method.clone().unwrap_or_else(|| "GET".to_string());
-    let rb = if method.eq_ignore_ascii_case("GET") {
-        client
-            .get(&url)
-            .query(&params_to_query(&body.params))
-            .header("accept", "application/json")
-            .header("Authorization", format!("Bearer {}", token))
+    let (status, cached, next_cursor, retry_after) =
+        (upstream.status, upstream.cached, upstream.next_cursor.clone(), upstream.retry_after);
+    let posts = upstream.posts.take();
+    let is_json = matches!(upstream.body, UpstreamBody::Json(_));
+    let data = store_upstream(app, provider.source(), body, upstream);
+    let mut resp_body = if is_json {
+        json!({"status": status, "data": data})
     } else {
-        let params_body = body.params.clone().unwrap_or(Value::Null);
-        client
-            .post(&url)
-            .header("accept", "application/json")
-            .header("Authorization", format!("Bearer {}", token))
-            .json(&params_body)
+        json!({"status": status, "data_text": data})
     };
-
-    let (resp, payload_opt) = execute_request_capture(&client, rb).await;
-
-    if let Some(payload_json) = payload_opt {
-        let session = state.session.clone();
-        let keyspace = state.keyspace.clone();
-        let source = "tikhub_generic".to_string();
-        let request_path = body.path.clone();
-        let params_json = body.params.as_ref().and_then(|v| serde_json::to_string(v).ok());
-        tokio::spawn(async move {
-            match insert_social_result(session, keyspace, source, request_path, params_json, payload_json).await {
-                Ok(()) => eprintln!("[Cassandra] Inserted social_result: tikhub_generic"),
-                Err(e) => eprintln!("[Cassandra] Insert failed: {}", e),
-            }
-        });
+    if let Some(posts) = posts {
+        resp_body["normalized"] = json!(&posts);
+        resp_body["next_cursor"] = json!(next_cursor);
+        if !cached {
+            store_posts(app, provider.source(), posts);
+        }
     }
 
-    resp
+    proxy_response(upstream_status_code(status), cached, retry_after, resp_body)
 }
 
-pub async fn proxy_tikhub_twitter(State(state): State<CassandraState>, Json(body): Json<ProxyRequest>) -> impl IntoResponse {
-    let token = match std::env::var("TIKHUB_TOKEN") {
-        Ok(v) => v,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": "Missing TIKHUB_TOKEN in environment"})),
-            )
-                .into_response();
-        }
-    };
-
-    let url = format!("{}{}", TIKHUB_TWITTER_BASE, body.path.trim_start_matches('/'));
-    let client = Client::new();
-
-    let method = body.method.clone().unwrap_or_else(|| "GET".to_string());
-    let rb = if method.eq_ignore_ascii_case("GET") {
-        client
-            .get(&url)
-            .query(&tikhub_twitter_query(&body.params))
-            .header("accept", "application/json")
-            .header("Authorization", format!("Bearer {}", token))
-    } else {
-        let params_body = body.params.clone().unwrap_or(Value::Null);
-        client
-            .post(&url)
-            .header("accept", "application/json")
-            .header("Authorization", format!("Bearer {}", token))
-            .json(&params_body)
-    };
+// Keeps the first bytes of a streamed body and stores them in `social_results` once the stream
+// ends. Bodies over the cap, binary bodies and broken transfers are not stored.
+struct RawCapture {
+    cassandra: CassandraState,
+    result: NewSocialResult,
+    buffer: Vec<u8>,
+    max_bytes: usize,
+    overflowed: bool,
+}
 
-    let (resp, payload_opt) = execute_request_capture(&client, rb).await;
+impl RawCapture {
+    fn push(&mut self, chunk: &[u8]) {
+        if self.overflowed {
+            return;
+        }
+        if self.buffer.len() + chunk.len() > self.max_bytes {
+            self.overflowed = true;
+            self.buffer = Vec::new();
+            return;
+        }
+        self.buffer.extend_from_slice(chunk);
+    }
 
-    if let Some(payload_json) = payload_opt {
-        let session = state.session.clone();
-        let keyspace = state.keyspace.clone();
-        let source = "tikhub_twitter".to_string();
-        let request_path = body.path.clone();
-        let params_json = body.params.as_ref().and_then(|v| serde_json::to_string(v).ok());
+    fn finish(self) {
+        if self.overflowed {
+            eprintln!("[Social] Raw {} body over {} bytes, not stored", self.result.source, self.max_bytes);
+            return;
+        }
+        let Ok(payload_json) = String::from_utf8(self.buffer) else {
+            return;
+        };
+        let result = NewSocialResult { payload_json, ..self.result };
+        let CassandraState { session, keyspace } = self.cassandra;
         tokio::spawn(async move {
-            match insert_social_result(session, keyspace, source, request_path, params_json, payload_json).await {
-                Ok(()) => eprintln!("[Cassandra] Inserted social_result: tikhub_twitter"),
+            let source_for_log = result.source.clone();
+            match insert_social_result(session, keyspace, result).await {
+                Ok(()) => eprintln!("[Cassandra] Inserted social_result: {}", source_for_log),
                 Err(e) => eprintln!("[Cassandra] Insert failed: {}", e),
             }
         });
     }
-
-    resp
 }
 
-pub async fn proxy_tikhub_tiktok(State(state): State<CassandraState>, Json(body): Json<ProxyRequest>) -> impl IntoResponse {
-    let token = match std::env::var("TIKHUB_TOKEN") {
-        Ok(v) => v,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": "Missing TIKHUB_TOKEN in environment"})),
-            )
-                .into_response();
-        }
-    };
-
-    let url = format!("{}{}", TIKHUB_TIKTOK_BASE, body.path.trim_start_matches('/'));
-    let client = Client::new();
-
-    let method = body.method.clone().unwrap_or_else(|| "GET".to_string());
-    let rb = if method.eq_ignore_ascii_case("GET") {
-        client
-            .get(&url)
-            .query(&tikhub_tiktok_query(&body.params))
-            .header("accept", "application/json")
-            .header("Authorization", format!("Bearer {}", token))
-    } else {
-        let params_body = body.params.clone().unwrap_or(Value::Null);
-        client
-            .post(&url)
-            .header("accept", "application/json")
-            .header("Authorization", format!("Bearer {}", token))
-            .json(&params_body)
+// Stream the upstream response back with its own status, content type and download headers.
+// Nothing is cached or normalized; the body is stored when it fits `SOCIAL_RAW_PERSIST_MAX_BYTES`.
+async fn proxy_raw(app: &AppState, provider: &dyn SocialProvider, client: &Client, body: &ProxyRequest) -> Response {
+    let upstream = match social::send_raw(provider, client, body).await {
+        Ok(upstream) => upstream,
+        Err(e) => return provider_error(e),
     };
 
-    let (resp, payload_opt) = execute_request_capture(&client, rb).await;
+    let status = StatusCode::from_u16(upstream.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    let mut headers = HeaderMap::new();
+    for name in [
+        header::CONTENT_TYPE,
+        header::CONTENT_LENGTH,
+        header::CONTENT_DISPOSITION,
+        header::ETAG,
+        header::LAST_MODIFIED,
+        header::RETRY_AFTER,
+    ] {
+        if let Some(value) = upstream.headers().get(name.as_str()).and_then(|v| HeaderValue::from_bytes(v.as_bytes()).ok()) {
+            headers.insert(name, value);
+        }
+    }
 
-    if let Some(payload_json) = payload_opt {
-        let session = state.session.clone();
-        let keyspace = state.keyspace.clone();
-        let source = "tikhub_tiktok".to_string();
-        let request_path = body.path.clone();
-        let params_json = body.params.as_ref().and_then(|v| serde_json::to_string(v).ok());
-        tokio::spawn(async move {
-            match insert_social_result(session, keyspace, source, request_path, params_json, payload_json).await {
-                Ok(()) => eprintln!("[Cassandra] Inserted social_result: tikhub_tiktok"),
-                Err(e) => eprintln!("[Cassandra] Insert failed: {}", e),
+    let max_bytes = app.social_raw.persist_max_bytes;
+    let capture = (max_bytes > 0).then(|| RawCapture {
+        cassandra: app.cassandra.clone(),
+        result: NewSocialResult {
+            source: provider.source(),
+            request_path: body.path.clone(),
+            params_json: body.params.as_ref().and_then(|v| serde_json::to_string(v).ok()),
+            payload_json: String::new(),
+            status: status.as_u16() as i32,
+            cache_key: None,
+        },
+        buffer: Vec::new(),
+        max_bytes,
+        overflowed: false,
+    });
+    // Upstream says it is larger than the cap; don't bother buffering
+    let capture = capture.filter(|_| upstream.content_length().is_none_or(|len| len <= max_bytes as u64));
+
+    let stream = futures::stream::unfold(Some((upstream, capture)), |state| async move {
+        let (mut upstream, mut capture) = state?;
+        match upstream.chunk().await {
+            Ok(Some(chunk)) => {
+                if let Some(capture) = capture.as_mut() {
+                    capture.push(&chunk);
+                }
+                Some((Ok(chunk), Some((upstream, capture))))
             }
-        });
-    }
+            Ok(None) => {
+                if let Some(capture) = capture {
+                    capture.finish();
+                }
+                None
+            }
+            // A broken transfer ends the stream with an error and is not stored
+            Err(e) => Some((Err(e), None)),
+        }
+    });
 
-    resp
+    (status, headers, Body::from_stream(stream)).into_response()
 }
 
-pub async fn proxy_rapidapi_instagram(State(state): State<CassandraState>, Json(body): Json<ProxyRequest>) -> impl IntoResponse {
-    let key = match std::env::var("RAPIDAPI_KEY") {
-        Ok(v) => v,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": "Missing RAPIDAPI_KEY in environment"})),
-            )
-                .into_response();
-        }
+// Follow cursors and return `{"status", "data": [page, ...], "normalized", "pages", "next_cursor"}`.
+// `x-cache` is `hit` only when every page came from the cache.
+async fn proxy_pages(
+    app: &AppState,
+    provider: &dyn SocialProvider,
+    client: &Client,
+    body: &ProxyRequest,
+    bypass_cache: bool,
+) -> Response {
+    let paged = match social::send_pages(&app.social_cache, provider, client, body, &app.social_paging, bypass_cache).await {
+        Ok(paged) => paged,
+        Err(e) => return provider_error(e),
     };
 
-    let url = format!("https://{}/{}", RAPIDAPI_INSTAGRAM_HOST, body.path.trim_start_matches('/'));
-    let client = Client::new();
-    let method = body.method.clone().unwrap_or_else(|| "GET".to_string());
-    let rb = if method.eq_ignore_ascii_case("GET") {
-        client
-            .get(&url)
-            .query(&params_to_query(&body.params))
-            .header("accept", "application/json")
-            .header("x-rapidapi-key", key.clone())
-            .header("x-rapidapi-host", RAPIDAPI_INSTAGRAM_HOST)
-    } else {
-        let params_body = body.params.clone().unwrap_or(Value::Null);
-        client
-            .post(&url)
-            .header("accept", "application/json")
-            .header("x-rapidapi-key", key.clone())
-            .header("x-rapidapi-host", RAPIDAPI_INSTAGRAM_HOST)
-            .json(&params_body)
-    };
+    let cached = paged.cached();
+    let page_count = paged.pages.len();
+    // Pages already fetched are worth returning even when a later one failed
+    let status = if page_count > 1 { StatusCode::OK } else { upstream_status_code(paged.status) };
+    let retry_after = paged.pages.last().and_then(|page| page.retry_after).filter(|_| page_count == 1);
+    let pages: Vec<Value> = paged
+        .pages
+        .into_iter()
+        .map(|page| store_upstream(app, provider.source(), body, page))
+        .collect();
+    let resp_body = json!({
+        "status": paged.status,
+        "data": pages,
+        "normalized": &paged.posts,
+        "pages": page_count,
+        "next_cursor": paged.next_cursor,
+    });
+    if !cached {
+        store_posts(app, provider.source(), paged.posts);
+    }
 
-    let (resp, payload_opt) = execute_request_capture(&client, rb).await;
+    proxy_response(status, cached, retry_after, resp_body)
+}
 
-    if let Some(payload_json) = payload_opt {
-        let session = state.session.clone();
-        let keyspace = state.keyspace.clone();
-        let source = "rapidapi_instagram".to_string();
-        let request_path = body.path.clone();
-        let params_json = body.params.as_ref().and_then(|v| serde_json::to_string(v).ok());
-        tokio::spawn(async move {
-            match insert_social_result(session, keyspace, source, request_path, params_json, payload_json).await {
-                Ok(()) => eprintln!("[Cassandra] Inserted social_result: rapidapi_instagram"),
-                Err(e) => eprintln!("[Cassandra] Insert failed: {}", e),
+// Proxy through a provider from the registry
+async fn proxy_registered(app: &AppState, name: &str, headers: &HeaderMap, body: ProxyRequest) -> Response {
+    match app.social_providers.get(name) {
+        Some(provider) => {
+            if let Err(resp) = check_proxy_target(app, provider.as_ref(), &body).await {
+                return resp;
             }
-        });
+            let client = app.http.provider(&provider.source());
+            proxy_through(app, provider.as_ref(), &client, &body, bypasses_cache(headers)).await
+        }
+        None => (StatusCode::NOT_FOUND, Json(json!({"error": format!("Unknown social provider {}", name)}))).into_response(),
     }
+}
 
-    resp
+#[derive(Deserialize)]
+pub struct TikHubGenericRequest {
+    pub service: String, // e.g., "twitter/web" or "tiktok/app/v3"
+    pub path: String,
+    #[serde(default)]
+    pub params: Option<Value>,
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default)]
+    pub raw: bool,
+    #[serde(default)]
+    pub retry_safe: bool,
 }
 
-pub async fn proxy_rapidapi_twitter_v24(State(state): State<CassandraState>, Json(body): Json<ProxyRequest>) -> impl IntoResponse {
-    let key = match std::env::var("RAPIDAPI_KEY") {
-        Ok(v) => v,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": "Missing RAPIDAPI_KEY in environment"})),
-            )
-                .into_response();
-        }
+pub async fn proxy_tikhub_generic(
+    State(app): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<TikHubGenericRequest>,
+) -> impl IntoResponse {
+    let service = body.service.trim_matches('/');
+    let request = ProxyRequest {
+        path: body.path.clone(),
+        params: body.params,
+        method: body.method,
+        raw: body.raw,
+        retry_safe: body.retry_safe,
+        ..Default::default()
     };
+    let provider = TikHubProvider::new("tikhub_generic", service);
+    if let Err(resp) = check_proxy_target(&app, &provider, &request).await {
+        return resp;
+    }
+    let client = app.http.provider(&provider.source());
+    proxy_through(&app, &provider, &client, &request, bypasses_cache(&headers)).await
+}
 
-    let url = format!("https://{}/{}", RAPIDAPI_TWITTER_V24_HOST, body.path.trim_start_matches('/'));
-    let client = Client::new();
-    let method = body.method.clone().unwrap_or_else(|| "GET".to_string());
-    let rb = if method.eq_ignore_ascii_case("GET") {
-        client
-            .get(&url)
-            .query(&params_to_query(&body.params))
-            .header("accept", "application/json")
-            .header("x-rapidapi-key", key.clone())
-            .header("x-rapidapi-host", RAPIDAPI_TWITTER_V24_HOST)
-    } else {
-        let params_body = body.params.clone().unwrap_or(Value::Null);
-        client
-            .post(&url)
-            .header("accept", "application/json")
-            .header("x-rapidapi-key", key.clone())
-            .header("x-rapidapi-host", RAPIDAPI_TWITTER_V24_HOST)
-            .json(&params_body)
-    };
+pub async fn proxy_tikhub_twitter(
+    State(app): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<ProxyRequest>,
+) -> impl IntoResponse {
+    proxy_registered(&app, "tikhub_twitter", &headers, body).await
+}
 
-    let (resp, payload_opt) = execute_request_capture(&client, rb).await;
+pub async fn proxy_tikhub_tiktok(
+    State(app): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<ProxyRequest>,
+) -> impl IntoResponse {
+    proxy_registered(&app, "tikhub_tiktok", &headers, body).await
+}
 
-    if let Some(payload_json) = payload_opt {
-        let session = state.session.clone();
-        let keyspace = state.keyspace.clone();
-        let source = "rapidapi_twitter_v24".to_string();
-        let request_path = body.path.clone();
-        let params_json = body.params.as_ref().and_then(|v| serde_json::to_string(v).ok());
-        tokio::spawn(async move {
-            match insert_social_result(session, keyspace, source, request_path, params_json, payload_json).await {
-                Ok(()) => eprintln!("[Cassandra] Inserted social_result: rapidapi_twitter_v24"),
-                Err(e) => eprintln!("[Cassandra] Insert failed: {}", e),
-            }
-        });
-    }
+pub async fn proxy_rapidapi_instagram(
+    State(app): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<ProxyRequest>,
+) -> impl IntoResponse {
+    proxy_registered(&app, "rapidapi_instagram", &headers, body).await
+}
 
-    resp
+pub async fn proxy_rapidapi_twitter_v24(
+    State(app): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<ProxyRequest>,
+) -> impl IntoResponse {
+    proxy_registered(&app, "rapidapi_twitter_v24", &headers, body).await
+}
+
+/// Proxy through any provider in the registry, including ones added via `SOCIAL_PROVIDERS`
+pub async fn proxy_provider(
+    State(app): State<AppState>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    Json(body): Json<ProxyRequest>,
+) -> impl IntoResponse {
+    proxy_registered(&app, &name, &headers, body).await
 }
 
 #[derive(Deserialize)]
@@ -455,56 +466,177 @@ pub struct RapidApiGenericRequest {
     pub params: Option<Value>,
     #[serde(default)]
     pub method: Option<String>,
+    #[serde(default)]
+    pub raw: bool,
+    #[serde(default)]
+    pub retry_safe: bool,
 }
 
-pub async fn proxy_rapidapi_generic(State(state): State<CassandraState>, Json(body): Json<RapidApiGenericRequest>) -> impl IntoResponse {
-    let key = match std::env::var("RAPIDAPI_KEY") {
-        Ok(v) => v,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": "Missing RAPIDAPI_KEY in environment"})),
-            )
-                .into_response();
-        }
+pub async fn proxy_rapidapi_generic(
+    State(app): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<RapidApiGenericRequest>,
+) -> impl IntoResponse {
+    let request = ProxyRequest {
+        path: body.path.clone(),
+        params: body.params,
+        method: body.method,
+        raw: body.raw,
+        retry_safe: body.retry_safe,
+        ..Default::default()
     };
+    let provider = RapidApiProvider::new(&format!("rapidapi_{}", body.host), &body.host);
+    if let Err(resp) = check_proxy_target(&app, &provider, &request).await {
+        return resp;
+    }
+    let client = app.http.provider(&provider.source());
+    proxy_through(&app, &provider, &client, &request, bypasses_cache(&headers)).await
+}
 
-    let url = format!("https://{}/{}", body.host, body.path.trim_start_matches('/'));
-    let client = Client::new();
-    let method = body.method.clone().unwrap_or_else(|| "GET".to_string());
-    let rb = if method.eq_ignore_ascii_case("GET") {
-        client
-            .get(&url)
-            .query(&params_to_query(&body.params))
-            .header("accept", "application/json")
-            .header("x-rapidapi-key", key.clone())
-            .header("x-rapidapi-host", body.host.clone())
-    } else {
-        let params_body = body.params.clone().unwrap_or(Value::Null);
-        client
-            .post(&url)
-            .header("accept", "application/json")
-            .header("x-rapidapi-key", key.clone())
-            .header("x-rapidapi-host", body.host.clone())
-            .json(&params_body)
-    };
+fn default_search_platforms() -> Vec<String> {
+    SEARCH_PLATFORMS.iter().map(|platform| platform.to_string()).collect()
+}
+
+#[derive(Clone, Deserialize)]
+pub struct SocialSearchRequest {
+    pub keyword: String,
+    #[serde(default = "default_search_platforms")]
+    pub platforms: Vec<String>,
+    #[serde(default)]
+    pub since: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub until: Option<DateTime<Utc>>,
+    /// Posts to collect per platform; follows cursors when set, otherwise one page each
+    #[serde(default)]
+    pub max_items: Option<usize>,
+}
 
-    let (resp, payload_opt) = execute_request_capture(&client, rb).await;
+// Helper function to build the search call for one platform with the provider that serves it
+fn search_target(
+    app: &AppState,
+    platform: &str,
+    search: &SocialSearchRequest,
+) -> Result<(Arc<dyn SocialProvider>, ProxyRequest), String> {
+    let (name, mut request) = social::search_request(platform, &search.keyword, search.since, search.until)
+        .ok_or_else(|| format!("Unsupported platform {}", platform))?;
+    let provider = app
+        .social_providers
+        .get(name)
+        .ok_or_else(|| format!("Social provider {} is not registered", name))?;
+    request.max_items = search.max_items;
+    request.max_pages = if search.max_items.is_some() { None } else { Some(1) };
+    Ok((provider, request))
+}
 
-    if let Some(payload_json) = payload_opt {
-        let session = state.session.clone();
-        let keyspace = state.keyspace.clone();
-        let source = format!("rapidapi_{}", body.host);
-        let request_path = body.path.clone();
-        let params_json = body.params.as_ref().and_then(|v| serde_json::to_string(v).ok());
-        tokio::spawn(async move {
-            let source_for_log = source.clone();
-            match insert_social_result(session, keyspace, source, request_path, params_json, payload_json).await {
-                Ok(()) => eprintln!("[Cassandra] Inserted social_result: rapidapi_generic {}", source_for_log),
-                Err(e) => eprintln!("[Cassandra] Insert failed: {}", e),
+// Helper function to run a vetted search on one platform and store what came back. Returns the
+// posts and the platform's `{"status", "count", "next_cursor", "cached"}` summary.
+async fn search_platform(
+    app: AppState,
+    provider: Arc<dyn SocialProvider>,
+    request: ProxyRequest,
+    bypass_cache: bool,
+) -> Result<(Vec<SocialPost>, Value), String> {
+    let client = app.http.provider(&provider.source());
+    let paged = social::send_pages(&app.social_cache, provider.as_ref(), &client, &request, &app.social_paging, bypass_cache)
+        .await
+        .map_err(|e| e.to_string())?;
+    let cached = paged.cached();
+    for page in paged.pages {
+        store_upstream(&app, provider.source(), &request, page);
+    }
+    if !cached {
+        store_posts(&app, provider.source(), paged.posts.clone());
+    }
+
+    if !(200..300).contains(&paged.status) {
+        return Err(format!("Upstream returned status {}", paged.status));
+    }
+    let summary = json!({
+        "status": paged.status,
+        "count": paged.posts.len(),
+        "next_cursor": paged.next_cursor,
+        "cached": cached,
+    });
+    Ok((paged.posts, summary))
+}
+
+/// Search a keyword on several platforms at once. Posts come back merged, newest first;
+/// a platform that fails is reported under `platforms` without failing the others.
+/// `x-cache` is `hit` only when every platform was served from the cache.
+pub async fn social_search(
+    State(app): State<AppState>,
+    headers: HeaderMap,
+    Json(mut body): Json<SocialSearchRequest>,
+) -> impl IntoResponse {
+    body.keyword = body.keyword.trim().to_string();
+    if body.keyword.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": "keyword is required"}))).into_response();
+    }
+    if let (Some(since), Some(until)) = (body.since, body.until) {
+        if since > until {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "since must be before until"}))).into_response();
+        }
+    }
+
+    let mut platforms = body.platforms.clone();
+    platforms.sort();
+    platforms.dedup();
+
+    // Every target goes through the same allowlist and outbound checks as the single-provider
+    // routes before anything is sent; a refused platform is reported and contributes no posts
+    let bypass_cache = bypasses_cache(&headers);
+    let mut summary = serde_json::Map::new();
+    let mut all_cached = true;
+    let mut searches = JoinSet::new();
+    for platform in platforms {
+        let (provider, request) = match search_target(&app, &platform, &body) {
+            Ok(target) => target,
+            Err(error) => {
+                all_cached = false;
+                summary.insert(platform, json!({"error": error}));
+                continue;
+            }
+        };
+        if let Err(error) = proxy_target_error(&app, provider.as_ref(), &request).await {
+            all_cached = false;
+            summary.insert(platform, json!({"error": error, "blocked": true}));
+            continue;
+        }
+
+        let app = app.clone();
+        searches.spawn(async move {
+            let result = search_platform(app, provider, request, bypass_cache).await;
+            (platform, result)
+        });
+    }
+
+    let mut posts: Vec<SocialPost> = Vec::new();
+    while let Some(joined) = searches.join_next().await {
+        let Ok((platform, result)) = joined else { continue };
+        let entry = match result {
+            Ok((platform_posts, entry)) => {
+                all_cached &= entry["cached"] == json!(true);
+                posts.extend(platform_posts);
+                entry
             }
+            Err(error) => {
+                all_cached = false;
+                json!({"error": error})
+            }
+        };
+        summary.insert(platform, entry);
+    }
+
+    // Posts without a timestamp cannot be placed in a window, so bounds exclude them
+    if body.since.is_some() || body.until.is_some() {
+        posts.retain(|post| {
+            post.created_at.is_some_and(|created_at| {
+                body.since.is_none_or(|since| created_at >= since) && body.until.is_none_or(|until| created_at <= until)
+            })
         });
     }
+    posts.sort_by(|a, b| b.created_at.cmp(&a.created_at));
 
-    resp
-}
\ No newline at end of file
+    let cached = all_cached && !summary.is_empty();
+    proxy_response(StatusCode::OK, cached, None, json!({"keyword": body.keyword, "posts": posts, "platforms": summary}))
+}
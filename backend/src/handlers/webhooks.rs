@@ -0,0 +1,130 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::Utc;
+use url::Url;
+use uuid::Uuid;
+
+use super::handle_error;
+use crate::db::{self, CassandraState};
+use crate::models::{ApiError, ApiResponse};
+use crate::outbound::check_url;
+use crate::webhooks::{WebhookDelivery, WebhookRequest, WebhookSubscription};
+
+// Helper function to validate a subscription body before it is stored. The target must pass the
+// outbound policy now, so a subscription pointing at an internal address is refused up front
+// instead of failing on every delivery.
+async fn validate_webhook_request(body: &WebhookRequest) -> Result<(), ApiError> {
+    let url = Url::parse(&body.url).map_err(|e| ApiError::ValidationError(format!("Invalid webhook url: {}", e)))?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(ApiError::ValidationError("Webhook url must use http or https".to_string()));
+    }
+    check_url(&url)
+        .await
+        .map_err(|e| ApiError::ValidationError(format!("Webhook url is not allowed: {}", e)))?;
+    if body.secret.trim().is_empty() {
+        return Err(ApiError::ValidationError("Webhook secret must not be empty".to_string()));
+    }
+    Ok(())
+}
+
+async fn load_webhook(state: &CassandraState, id: Uuid) -> Result<WebhookSubscription, ApiError> {
+    db::get_webhook(&state.session, &state.keyspace, id)
+        .await
+        .map_err(|e| ApiError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| ApiError::NotFound(format!("Webhook {} not found", id)))
+}
+
+pub async fn create_webhook(
+    State(state): State<CassandraState>,
+    Json(body): Json<WebhookRequest>,
+) -> impl IntoResponse {
+    if let Err(err) = validate_webhook_request(&body).await {
+        return handle_error::<WebhookSubscription>(err).into_response();
+    }
+
+    let webhook = WebhookSubscription {
+        id: Uuid::new_v4(),
+        url: body.url,
+        secret: body.secret,
+        keywords: body.keywords,
+        domains: body.domains,
+        enabled: body.enabled.unwrap_or(true),
+        created_at: Utc::now(),
+    };
+
+    match db::insert_webhook(&state.session, &state.keyspace, &webhook).await {
+        Ok(()) => (StatusCode::CREATED, Json(ApiResponse::success(webhook))).into_response(),
+        Err(e) => handle_error::<WebhookSubscription>(ApiError::DatabaseError(e.to_string())).into_response(),
+    }
+}
+
+pub async fn list_webhooks(State(state): State<CassandraState>) -> impl IntoResponse {
+    match db::list_webhooks(&state.session, &state.keyspace).await {
+        Ok(webhooks) => (StatusCode::OK, Json(ApiResponse::success(webhooks))).into_response(),
+        Err(e) => handle_error::<Vec<WebhookSubscription>>(ApiError::DatabaseError(e.to_string())).into_response(),
+    }
+}
+
+pub async fn get_webhook(State(state): State<CassandraState>, Path(id): Path<Uuid>) -> impl IntoResponse {
+    match load_webhook(&state, id).await {
+        Ok(webhook) => (StatusCode::OK, Json(ApiResponse::success(webhook))).into_response(),
+        Err(err) => handle_error::<WebhookSubscription>(err).into_response(),
+    }
+}
+
+pub async fn delete_webhook(State(state): State<CassandraState>, Path(id): Path<Uuid>) -> impl IntoResponse {
+    if let Err(err) = load_webhook(&state, id).await {
+        return handle_error::<WebhookSubscription>(err).into_response();
+    }
+
+    match db::delete_webhook(&state.session, &state.keyspace, id).await {
+        Ok(()) => (StatusCode::OK, Json(ApiResponse::success(id))).into_response(),
+        Err(e) => handle_error::<Uuid>(ApiError::DatabaseError(e.to_string())).into_response(),
+    }
+}
+
+pub async fn list_webhook_deliveries(State(state): State<CassandraState>, Path(id): Path<Uuid>) -> impl IntoResponse {
+    match db::list_webhook_deliveries(&state.session, &state.keyspace, id).await {
+        Ok(deliveries) => (StatusCode::OK, Json(ApiResponse::success(deliveries))).into_response(),
+        Err(e) => handle_error::<Vec<WebhookDelivery>>(ApiError::DatabaseError(e.to_string())).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(url: &str) -> WebhookRequest {
+        WebhookRequest {
+            url: url.to_string(),
+            secret: "s3cret".to_string(),
+            keywords: vec!["rust".to_string()],
+            domains: vec![],
+            enabled: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_internal_webhook_targets() {
+        for url in ["http://127.0.0.1:8080/hook", "http://169.254.169.254/latest", "http://[::1]/hook", "http://metadata.internal/"] {
+            let err = validate_webhook_request(&request(url)).await.unwrap_err();
+            assert!(matches!(err, ApiError::ValidationError(ref m) if m.contains("not allowed")), "{}: {}", url, err);
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_schemes_and_empty_secrets() {
+        assert!(validate_webhook_request(&request("ftp://example.com/hook")).await.is_err());
+        let mut body = request("http://93.184.216.34/hook");
+        body.secret = "  ".to_string();
+        assert!(validate_webhook_request(&body).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn accepts_public_webhook_targets() {
+        assert!(validate_webhook_request(&request("https://93.184.216.34/hook")).await.is_ok());
+    }
+}
@@ -1,3 +1,4 @@
+mod auth;
 mod config;
 mod db;
 mod handlers;
@@ -5,9 +6,14 @@ mod models;
 mod routes;
 mod crawler;
 mod kafka;
+mod outbound;
+mod scheduler;
+mod social;
+mod state;
+mod webhooks;
 
 use axum::http::{
-    header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE},
+    header::{HeaderName, ACCEPT, AUTHORIZATION, CACHE_CONTROL, CONTENT_TYPE},
     Method,
 };
 use std::net::SocketAddr;
@@ -20,15 +26,38 @@ async fn main() {
     
     // Initialize Cassandra connection
     let cassandra_state = db::init_db().await.expect("Failed to connect to Cassandra");
+    let app_state = match state::AppState::new(cassandra_state) {
+        Ok(state) => state,
+        Err(e) => {
+            eprintln!("[Config] {}; fix or unset it", e);
+            std::process::exit(1);
+        }
+    };
+
+    // With auth on, something must be able to authenticate: keys are issued through /admin,
+    // so without ADMIN_API_KEY or a JWT issuer every request would be rejected
+    if app_state.api_keys.is_enabled() && !app_state.api_keys.has_admin_key() && !app_state.jwt.is_configured() {
+        eprintln!("[Auth] AUTH_ENABLED is on but neither ADMIN_API_KEY nor JWT_* is set; set ADMIN_API_KEY, configure JWT, or set AUTH_ENABLED=false");
+        std::process::exit(1);
+    }
+
+    // Start the recurring crawl scheduler (set SCHEDULER_ENABLED=false to opt a replica out)
+    let scheduler_enabled = std::env::var("SCHEDULER_ENABLED")
+        .map(|v| v != "false" && v != "0")
+        .unwrap_or(true);
+    if scheduler_enabled {
+        tokio::spawn(scheduler::run_scheduler(app_state.clone()));
+    }
     
     // Setup CORS
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
-        .allow_headers([AUTHORIZATION, ACCEPT, CONTENT_TYPE]);
+        .allow_headers([AUTHORIZATION, ACCEPT, CONTENT_TYPE, CACHE_CONTROL])
+        .expose_headers([HeaderName::from_static("x-cache")]);
     
     // Build application with routes
-    let app = routes::create_routes(cassandra_state).layer(cors);
+    let app = routes::create_routes(app_state).layer(cors);
     
     // Run the server
     let port = std::env::var("SERVER_PORT")
@@ -52,6 +52,7 @@ pub enum ApiError {
     DatabaseError(String),
     NotFound(String),
     ValidationError(String),
+    Conflict(String),
     InternalServerError(String),
 }
 
@@ -61,6 +62,7 @@ impl fmt::Display for ApiError {
             ApiError::DatabaseError(e) => write!(f, "Database error: {}", e),
             ApiError::NotFound(e) => write!(f, "Not found: {}", e),
             ApiError::ValidationError(e) => write!(f, "Validation error: {}", e),
+            ApiError::Conflict(e) => write!(f, "Conflict: {}", e),
             ApiError::InternalServerError(e) => write!(f, "Internal server error: {}", e),
         }
     }
@@ -0,0 +1,235 @@
+use reqwest::{Client, ClientBuilder, Proxy};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use url::Url;
+
+use super::{guarded_client_builder, outbound_policy, proxied_client_builder, OutboundError, OutboundPolicy};
+use crate::config::ConfigError;
+
+/// Settings for every outbound HTTP client, read from the environment once
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    pub connect_timeout: Duration,
+    /// Total time for a request, including reading the body; callers may set a shorter one
+    pub timeout: Duration,
+    pub proxy: Option<String>,
+    pub user_agent: String,
+    pub pool_max_idle_per_host: usize,
+    pub pool_idle_timeout: Duration,
+}
+
+impl HttpConfig {
+    pub fn from_env() -> Self {
+        let seconds = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|v| *v > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(Duration::from_secs(default))
+        };
+        let pool_max_idle_per_host = std::env::var("HTTP_POOL_MAX_IDLE_PER_HOST")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(16);
+
+        Self {
+            connect_timeout: seconds("HTTP_CONNECT_TIMEOUT_SECONDS", 10),
+            timeout: seconds("HTTP_TIMEOUT_SECONDS", 60),
+            proxy: std::env::var("HTTP_PROXY_URL").ok().filter(|v| !v.trim().is_empty()),
+            user_agent: std::env::var("HTTP_USER_AGENT")
+                .ok()
+                .filter(|v| !v.trim().is_empty())
+                .unwrap_or_else(|| format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))),
+            pool_max_idle_per_host,
+            pool_idle_timeout: seconds("HTTP_POOL_IDLE_TIMEOUT_SECONDS", 90),
+        }
+    }
+}
+
+/// Outbound HTTP clients shared across the app. All of them are held to the outbound policy and
+/// share timeouts, proxy, user agent and gzip/brotli support. Each social provider gets its own
+/// connection pool so a slow upstream cannot starve the others; crawl-side fetches and webhook
+/// deliveries share one.
+pub struct HttpClients {
+    config: HttpConfig,
+    policy: Arc<OutboundPolicy>,
+    shared: Client,
+    trusted: Client,
+    providers: Mutex<HashMap<String, Client>>,
+}
+
+impl HttpClients {
+    /// Build the clients from the environment. Fails when `HTTP_PROXY_URL` is not a usable proxy.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::with_policy(Arc::new(outbound_policy().clone()))
+    }
+
+    /// Clients configured from the environment but held to an explicitly built policy
+    pub fn with_policy(policy: Arc<OutboundPolicy>) -> Result<Self, ConfigError> {
+        Self::new(HttpConfig::from_env(), policy)
+    }
+
+    fn new(config: HttpConfig, policy: Arc<OutboundPolicy>) -> Result<Self, ConfigError> {
+        let shared = build(configured_builder(&config, &policy)?)?;
+        let trusted = build(with_settings(Client::builder(), &config)?)?;
+
+        Ok(Self {
+            config,
+            policy,
+            shared,
+            trusted,
+            providers: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Outbound policy these clients are held to, for checking destinations before a request
+    pub fn policy(&self) -> &Arc<OutboundPolicy> {
+        &self.policy
+    }
+
+    /// Check a destination before sending it through the shared or provider clients. Without a
+    /// proxy the resolver guard covers hostnames and only the static checks run here; behind
+    /// `HTTP_PROXY_URL` the proxy resolves them, so the full `check_url` runs instead.
+    pub async fn check_target(&self, url: &Url) -> Result<(), OutboundError> {
+        if self.config.proxy.is_some() {
+            self.policy.check_url(url).await
+        } else {
+            self.policy.check_static(url).map(|_| ())
+        }
+    }
+
+    /// Client for crawl-side requests (robots.txt, conditional GETs) and webhook deliveries
+    pub fn shared(&self) -> Client {
+        self.shared.clone()
+    }
+
+    /// Client for destinations the operator configured, such as `JWT_JWKS_URL`, which may live on
+    /// an internal network. It is not held to the outbound policy, so never send it a URL that
+    /// came from a request.
+    pub fn trusted(&self) -> Client {
+        self.trusted.clone()
+    }
+
+    /// Pooled client for one social provider, created on first use
+    pub fn provider(&self, source: &str) -> Client {
+        let mut providers = self.providers.lock().unwrap();
+        if let Some(client) = providers.get(source) {
+            return client.clone();
+        }
+        // Same settings as the shared client, which already built fine
+        let client = configured_builder(&self.config, &self.policy)
+            .ok()
+            .and_then(|builder| builder.build().ok())
+            .unwrap_or_else(|| self.shared.clone());
+        providers.insert(source.to_string(), client.clone());
+        client
+    }
+}
+
+// Helper function to apply the shared settings on top of the guarded builder, or the proxied
+// one when `HTTP_PROXY_URL` is set so the proxy's own address is resolved without the guard
+fn configured_builder(config: &HttpConfig, policy: &Arc<OutboundPolicy>) -> Result<ClientBuilder, ConfigError> {
+    let builder = match config.proxy {
+        Some(_) => proxied_client_builder(policy.clone()),
+        None => guarded_client_builder(policy.clone()),
+    };
+    with_settings(builder, config)
+}
+
+// Helper function to build a client, reporting failures as configuration errors
+fn build(builder: ClientBuilder) -> Result<Client, ConfigError> {
+    builder.build().map_err(|e| ConfigError::HttpClient(e.to_string()))
+}
+
+// Helper function to apply timeouts, pooling, user agent, compression and proxy to a builder
+fn with_settings(builder: ClientBuilder, config: &HttpConfig) -> Result<ClientBuilder, ConfigError> {
+    let mut builder = builder
+        .connect_timeout(config.connect_timeout)
+        .timeout(config.timeout)
+        .user_agent(config.user_agent.as_str())
+        .pool_max_idle_per_host(config.pool_max_idle_per_host)
+        .pool_idle_timeout(config.pool_idle_timeout)
+        .gzip(true)
+        .brotli(true);
+    if let Some(proxy) = &config.proxy {
+        let proxy = Proxy::all(proxy.as_str()).map_err(|e| ConfigError::InvalidProxy(e.to_string()))?;
+        builder = builder.proxy(proxy);
+    }
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, response::Redirect, routing::get, Router};
+
+    fn config(proxy: Option<String>) -> HttpConfig {
+        HttpConfig {
+            connect_timeout: Duration::from_secs(5),
+            timeout: Duration::from_secs(5),
+            proxy,
+            user_agent: "test".to_string(),
+            pool_max_idle_per_host: 1,
+            pool_idle_timeout: Duration::from_secs(5),
+        }
+    }
+
+    // Helper function to serve a forward proxy on loopback that answers every request itself
+    async fn serve_proxy() -> String {
+        let app = Router::new()
+            .route("/", get(|| async { "proxied" }))
+            .route("/home", get(|| async { Redirect::temporary("http://site.example/") }))
+            .route("/away", get(|| async { Redirect::temporary("http://elsewhere.example/") }))
+            .route("/loopback", get(|| async { Redirect::temporary("http://127.0.0.1/") }))
+            .fallback(|| async { StatusCode::NOT_FOUND });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://localhost:{}", port)
+    }
+
+    #[test]
+    fn an_invalid_proxy_url_is_a_config_error() {
+        let result = HttpClients::new(config(Some("http://[::1".to_string())), Arc::new(OutboundPolicy::default()));
+        assert!(matches!(result, Err(ConfigError::InvalidProxy(_))));
+    }
+
+    #[tokio::test]
+    async fn a_proxy_on_a_private_address_is_reachable() {
+        let proxy = serve_proxy().await;
+        let http = HttpClients::new(config(Some(proxy)), Arc::new(OutboundPolicy::default())).unwrap();
+
+        let resp = http.shared().get("http://site.example/").send().await.unwrap();
+        assert_eq!(resp.text().await.unwrap(), "proxied");
+    }
+
+    #[tokio::test]
+    async fn redirects_behind_a_proxy_stay_on_hosts_already_visited() {
+        let proxy = serve_proxy().await;
+        let http = HttpClients::new(config(Some(proxy)), Arc::new(OutboundPolicy::default())).unwrap();
+
+        let resp = http.shared().get("http://site.example/home").send().await.unwrap();
+        assert_eq!(resp.text().await.unwrap(), "proxied");
+        assert!(http.shared().get("http://site.example/away").send().await.is_err());
+        assert!(http.shared().get("http://site.example/loopback").send().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn targets_are_checked_before_sending() {
+        let policy = Arc::new(OutboundPolicy::default());
+        let direct = HttpClients::new(config(None), policy.clone()).unwrap();
+        let proxied = HttpClients::new(config(Some("http://proxy.example:3128".to_string())), policy).unwrap();
+        let url = |value: &str| Url::parse(value).unwrap();
+
+        for http in [&direct, &proxied] {
+            assert!(http.check_target(&url("http://127.0.0.1/")).await.is_err());
+            assert!(http.check_target(&url("http://metadata.internal/")).await.is_err());
+            assert!(http.check_target(&url("http://93.184.215.14/")).await.is_ok());
+        }
+        // The proxy resolves hostnames, so they are resolved and checked up front instead
+        assert!(direct.check_target(&url("http://unresolvable.invalid/")).await.is_ok());
+        assert!(proxied.check_target(&url("http://unresolvable.invalid/")).await.is_err());
+    }
+}
@@ -0,0 +1,374 @@
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use reqwest::ClientBuilder;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, OnceLock};
+use url::{Host, Url};
+
+mod clients;
+
+pub use clients::HttpClients;
+
+// Redirects followed by guarded clients before giving up
+const MAX_REDIRECTS: usize = 10;
+
+#[derive(Debug, thiserror::Error)]
+pub enum OutboundError {
+    #[error("Unsupported URL scheme: {0}")]
+    UnsupportedScheme(String),
+
+    #[error("URL has no host: {0}")]
+    MissingHost(String),
+
+    #[error("Destination {host} resolves to a blocked address ({addr})")]
+    BlockedAddress { host: String, addr: IpAddr },
+
+    #[error("Destination {0} is not allowed")]
+    BlockedHost(String),
+
+    #[error("Could not resolve {host}: {reason}")]
+    Resolve { host: String, reason: String },
+
+    #[error("Redirect to {0} cannot be checked behind the HTTP proxy")]
+    UncheckedRedirect(String),
+}
+
+// An allowlisted network in CIDR form
+#[derive(Debug, Clone)]
+struct Network {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Network {
+    fn parse(value: &str) -> Option<Self> {
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr.parse::<IpAddr>().ok()?, prefix.parse::<u8>().ok()?),
+            None => {
+                let addr = value.parse::<IpAddr>().ok()?;
+                (addr, if addr.is_ipv4() { 32 } else { 128 })
+            }
+        };
+        let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+        (prefix <= max_prefix).then_some(Self { addr, prefix })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Destinations that may be reached even though they are private, from `OUTBOUND_ALLOWLIST`
+/// (comma-separated hostnames, IPs or CIDR ranges)
+#[derive(Debug, Clone, Default)]
+pub struct OutboundPolicy {
+    allowed_hosts: Vec<String>,
+    allowed_networks: Vec<Network>,
+}
+
+/// The process-wide outbound policy
+pub fn outbound_policy() -> &'static OutboundPolicy {
+    static POLICY: OnceLock<OutboundPolicy> = OnceLock::new();
+    POLICY.get_or_init(OutboundPolicy::from_env)
+}
+
+impl OutboundPolicy {
+    pub fn from_env() -> Self {
+        Self::from_allowlist(&std::env::var("OUTBOUND_ALLOWLIST").unwrap_or_default())
+    }
+
+    /// Policy allowing the comma-separated hostnames, IPs or CIDR ranges in `entries`
+    pub fn from_allowlist(entries: &str) -> Self {
+        let mut policy = Self::default();
+        for entry in entries.split(',').map(|e| e.trim().to_lowercase()).filter(|e| !e.is_empty()) {
+            match Network::parse(&entry) {
+                Some(network) => policy.allowed_networks.push(network),
+                None => policy.allowed_hosts.push(entry.trim_end_matches('.').to_string()),
+            }
+        }
+        policy
+    }
+
+    fn host_allowed(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_lowercase();
+        self.allowed_hosts.contains(&host)
+    }
+
+    fn ip_allowed(&self, ip: IpAddr) -> bool {
+        !is_blocked_ip(ip) || self.allowed_networks.iter().any(|network| network.contains(ip))
+    }
+
+    /// Synchronous part of the check: scheme, IP literals and names that are always internal.
+    /// Returns the hostname that still needs resolving, if any.
+    fn check_static(&self, url: &Url) -> Result<Option<String>, OutboundError> {
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return Err(OutboundError::UnsupportedScheme(url.scheme().to_string()));
+        }
+
+        match url.host() {
+            None => Err(OutboundError::MissingHost(url.to_string())),
+            Some(Host::Ipv4(ip)) => self.check_ip(&ip.to_string(), IpAddr::V4(ip)).map(|_| None),
+            Some(Host::Ipv6(ip)) => self.check_ip(&ip.to_string(), IpAddr::V6(ip)).map(|_| None),
+            Some(Host::Domain(domain)) => {
+                if self.host_allowed(domain) {
+                    return Ok(None);
+                }
+                if is_internal_name(domain) {
+                    return Err(OutboundError::BlockedHost(domain.to_string()));
+                }
+                Ok(Some(domain.to_string()))
+            }
+        }
+    }
+
+    fn check_ip(&self, host: &str, ip: IpAddr) -> Result<(), OutboundError> {
+        if self.ip_allowed(ip) {
+            Ok(())
+        } else {
+            Err(OutboundError::BlockedAddress {
+                host: host.to_string(),
+                addr: ip,
+            })
+        }
+    }
+
+    /// Synchronous check for links discovered while crawling: the static checks must pass, and a
+    /// hostname that would still need resolving must be one that already passed `check_url`
+    pub fn allows_link(&self, url: &Url, checked_hosts: &[String]) -> bool {
+        match self.check_static(url) {
+            Ok(None) => true,
+            Ok(Some(host)) => checked_hosts.iter().any(|checked| checked.eq_ignore_ascii_case(&host)),
+            Err(_) => false,
+        }
+    }
+
+    /// Check that a URL points at a public destination, resolving its hostname. Every resolved
+    /// address must be public so a name cannot smuggle in a private one.
+    pub async fn check_url(&self, url: &Url) -> Result<(), OutboundError> {
+        let host = match self.check_static(url)? {
+            Some(host) => host,
+            None => return Ok(()),
+        };
+
+        let port = url.port_or_known_default().unwrap_or(443);
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), port))
+            .await
+            .map_err(|e| OutboundError::Resolve {
+                host: host.clone(),
+                reason: e.to_string(),
+            })?
+            .collect();
+        if addrs.is_empty() {
+            return Err(OutboundError::Resolve {
+                host,
+                reason: "no addresses".to_string(),
+            });
+        }
+        for addr in addrs {
+            self.check_ip(&host, addr.ip())?;
+        }
+        Ok(())
+    }
+}
+
+/// Check a URL against the process-wide outbound policy
+pub async fn check_url(url: &Url) -> Result<(), OutboundError> {
+    outbound_policy().check_url(url).await
+}
+
+/// Per-crawl memo of which hosts passed the outbound check, so a crawl resolves each host once
+pub struct HostCheckCache {
+    policy: Arc<OutboundPolicy>,
+    checked: HashMap<String, bool>,
+}
+
+impl HostCheckCache {
+    pub fn new(policy: Arc<OutboundPolicy>) -> Self {
+        Self {
+            policy,
+            checked: HashMap::new(),
+        }
+    }
+
+    pub async fn allows(&mut self, url: &str) -> bool {
+        let url = match Url::parse(url) {
+            Ok(url) => url,
+            Err(_) => return false,
+        };
+        let key = format!("{}://{}", url.scheme(), url.host_str().unwrap_or_default());
+        if let Some(allowed) = self.checked.get(&key) {
+            return *allowed;
+        }
+        let allowed = self.policy.check_url(&url).await.is_ok();
+        self.checked.insert(key, allowed);
+        allowed
+    }
+}
+
+// Helper function to catch names that only ever point inside our network
+fn is_internal_name(host: &str) -> bool {
+    let host = host.trim_end_matches('.').to_lowercase();
+    host == "localhost"
+        || host.ends_with(".localhost")
+        || host.ends_with(".local")
+        || host.ends_with(".internal")
+        || host == "metadata"
+}
+
+/// Loopback, private, link-local (including cloud metadata), CGNAT, multicast and other
+/// non-public ranges
+pub fn is_blocked_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_blocked_ipv4(ip),
+        IpAddr::V6(ip) => is_blocked_ipv6(ip),
+    }
+}
+
+fn is_blocked_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || a == 0
+        || (a == 100 && (64..128).contains(&b)) // Carrier-grade NAT
+        || (a == 192 && b == 0 && c == 0) // IETF protocol assignments
+        || (a == 198 && (b == 18 || b == 19)) // Benchmarking
+        || a >= 240 // Reserved
+}
+
+fn is_blocked_ipv6(ip: Ipv6Addr) -> bool {
+    if let Some(mapped) = ip.to_ipv4_mapped() {
+        return is_blocked_ipv4(mapped);
+    }
+    let first = ip.segments()[0];
+    ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || (first & 0xfe00) == 0xfc00 // Unique local, includes fd00:ec2::254
+        || (first & 0xffc0) == 0xfe80 // Link-local
+        || (first == 0x2001 && ip.segments()[1] == 0x0db8) // Documentation
+        || (first == 0x0064 && ip.segments()[1] == 0xff9b) // NAT64 can reach IPv4 private space
+        || first == 0x2002 // 6to4 relays can reach IPv4 private space
+        || (first == 0x2001 && ip.segments()[1] == 0x0000) // Teredo, likewise
+}
+
+// DNS resolver that drops blocked addresses, so a name cannot rebind to a private address
+// between our check and the actual connection
+struct GuardedResolver {
+    policy: Arc<OutboundPolicy>,
+}
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let policy = self.policy.clone();
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            if !policy.host_allowed(&host) && is_internal_name(&host) {
+                return Err(Box::new(OutboundError::BlockedHost(host)) as Box<dyn std::error::Error + Send + Sync>);
+            }
+
+            let resolved: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if !policy.host_allowed(&host) {
+                if let Some(addr) = resolved.iter().find(|addr| !policy.ip_allowed(addr.ip())) {
+                    let addr = addr.ip();
+                    return Err(Box::new(OutboundError::BlockedAddress { host, addr }) as Box<dyn std::error::Error + Send + Sync>);
+                }
+            }
+            Ok(Box::new(resolved.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// A reqwest client builder whose DNS lookups and redirects are held to `policy`.
+/// Use this for every request whose destination comes from user input.
+pub fn guarded_client_builder(policy: Arc<OutboundPolicy>) -> ClientBuilder {
+    reqwest::Client::builder()
+        .dns_resolver(Arc::new(GuardedResolver { policy: policy.clone() }))
+        .redirect(guarded_redirects(policy, false))
+}
+
+/// A reqwest client builder for sending through a forward proxy. The proxy resolves target
+/// hostnames itself, so the resolver guard would only ever see the proxy's own name: it is left
+/// out, and targets must pass `check_url` before they are sent instead. Redirects may only move
+/// to IP literals that pass the policy or to hosts the request has already been to.
+pub fn proxied_client_builder(policy: Arc<OutboundPolicy>) -> ClientBuilder {
+    reqwest::Client::builder().redirect(guarded_redirects(policy, true))
+}
+
+// Helper function to hold redirects to `policy`. IP-literal targets never hit the resolver, so
+// they are checked here; behind a proxy nothing is resolved locally, so unseen hosts are refused.
+fn guarded_redirects(policy: Arc<OutboundPolicy>, proxied: bool) -> Policy {
+    Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            return attempt.error("too many redirects");
+        }
+        match policy.check_static(attempt.url()) {
+            Ok(Some(host)) if proxied && !visited(attempt.previous(), &host) => {
+                attempt.error(OutboundError::UncheckedRedirect(host))
+            }
+            Ok(_) => attempt.follow(),
+            Err(e) => attempt.error(e),
+        }
+    })
+}
+
+// Helper function to tell whether a redirect chain has already been to `host`
+fn visited(previous: &[Url], host: &str) -> bool {
+    previous
+        .iter()
+        .any(|url| url.host_str().is_some_and(|seen| seen.eq_ignore_ascii_case(host)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(value: &str) -> Url {
+        Url::parse(value).unwrap()
+    }
+
+    #[test]
+    fn allowlist_admits_listed_hosts_and_networks() {
+        let policy = OutboundPolicy::from_allowlist("Intranet.Example , 10.1.0.0/16,127.0.0.1");
+        assert!(policy.host_allowed("intranet.example."));
+        assert!(policy.ip_allowed("10.1.2.3".parse().unwrap()));
+        assert!(policy.ip_allowed("127.0.0.1".parse().unwrap()));
+        assert!(!policy.ip_allowed("10.2.0.1".parse().unwrap()));
+        assert!(!policy.ip_allowed("127.0.0.2".parse().unwrap()));
+    }
+
+    #[test]
+    fn discovered_links_need_a_checked_host() {
+        let policy = OutboundPolicy::default();
+        let checked = vec!["example.com".to_string()];
+        assert!(policy.allows_link(&url("https://Example.com/news/1"), &checked));
+        assert!(!policy.allows_link(&url("https://other.example.org/"), &checked));
+        assert!(!policy.allows_link(&url("http://127.0.0.1/admin"), &checked));
+        assert!(!policy.allows_link(&url("http://169.254.169.254/latest/meta-data"), &checked));
+        assert!(!policy.allows_link(&url("http://service.internal/"), &checked));
+        assert!(!policy.allows_link(&url("ftp://example.com/file"), &checked));
+    }
+
+    #[tokio::test]
+    async fn explicit_policy_allows_loopback_without_touching_the_environment() {
+        let policy = OutboundPolicy::from_allowlist("127.0.0.1");
+        assert!(policy.check_url(&url("http://127.0.0.1:8080/")).await.is_ok());
+        assert!(OutboundPolicy::default().check_url(&url("http://127.0.0.1:8080/")).await.is_err());
+    }
+}
@@ -1,21 +1,40 @@
 use axum::{
+    middleware,
     routing::{get, post, put, delete},
     Router,
 };
-use crate::db::CassandraState;
+use crate::auth;
+use crate::state::AppState;
 
 use crate::handlers;
 
-pub fn create_routes(state: CassandraState) -> Router {
-    Router::new()
-        .route("/health", get(handlers::health_check))
+pub fn create_routes(state: AppState) -> Router {
+    let public = Router::new()
+        .route("/health", get(handlers::health_check));
         // User endpoints temporarily disabled pending Cassandra schema
         // .route("/users", get(handlers::get_users))
         // .route("/users", post(handlers::create_user))
         // .route("/users/{id}", get(handlers::get_user_by_id))
         // .route("/users/{id}", put(handlers::update_user))
         // .route("/users/{id}", delete(handlers::delete_user))
+
+    // Client endpoints: reads need the viewer role, everything else the crawler role.
+    // API keys are metered on /crawl and /social/*.
+    let client = Router::new()
         .route("/crawl", post(handlers::crawl_website))
+        .route("/results/{id}", get(handlers::get_crawl_result))
+        // Recurring crawl schedules
+        .route("/schedules", get(handlers::list_schedules).post(handlers::create_schedule))
+        .route(
+            "/schedules/{id}",
+            get(handlers::get_schedule)
+                .put(handlers::update_schedule)
+                .delete(handlers::delete_schedule),
+        )
+        // Keyword alert webhooks
+        .route("/webhooks", get(handlers::list_webhooks).post(handlers::create_webhook))
+        .route("/webhooks/{id}", get(handlers::get_webhook).delete(handlers::delete_webhook))
+        .route("/webhooks/{id}/deliveries", get(handlers::list_webhook_deliveries))
         // Social media proxy endpoints
         .route("/social/tikhub/generic", post(handlers::proxy_tikhub_generic))
         .route("/social/tikhub/twitter", post(handlers::proxy_tikhub_twitter))
@@ -23,5 +42,19 @@ pub fn create_routes(state: CassandraState) -> Router {
         .route("/social/rapidapi/instagram", post(handlers::proxy_rapidapi_instagram))
         .route("/social/rapidapi/twitter-v24", post(handlers::proxy_rapidapi_twitter_v24))
         .route("/social/rapidapi/generic", post(handlers::proxy_rapidapi_generic))
-        .with_state(state)
-}
\ No newline at end of file
+        .route("/social/providers/{name}", post(handlers::proxy_provider))
+        .route("/social/search", post(handlers::social_search))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::authorize));
+
+    // Admin endpoints require the admin role
+    let admin = Router::new()
+        .route("/admin/politeness", get(handlers::politeness_status))
+        .route("/admin/social/breakers", get(handlers::social_breaker_status))
+        .route("/admin/social/credentials", get(handlers::social_credential_usage))
+        .route("/admin/api-keys", get(handlers::list_api_keys).post(handlers::create_api_key))
+        .route("/admin/api-keys/{id}", delete(handlers::revoke_api_key))
+        .route("/admin/api-keys/{id}/usage", get(handlers::get_api_key_usage))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::require_admin));
+
+    public.merge(client).merge(admin).with_state(state)
+}
@@ -0,0 +1,219 @@
+use chrono::{DateTime, Utc};
+use cron::Schedule as CronSchedule;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use tokio::time::{interval, Duration};
+use uuid::Uuid;
+
+use crate::crawler::CrawlRequest;
+use crate::db::{claim_schedule_run, extend_schedule_lock, list_schedules, record_schedule_run};
+use crate::handlers::execute_crawl;
+use crate::state::AppState;
+
+// Shortest interval accepted for recurring crawls
+const MIN_INTERVAL_SECONDS: u64 = 60;
+// Slack on top of the crawl time budget before a run lock is considered abandoned
+const LOCK_MARGIN_SECONDS: i64 = 300;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub request: CrawlRequest,
+    pub cron: Option<String>,
+    pub interval_seconds: Option<u64>,
+    pub enabled: bool,
+    pub next_run_at: DateTime<Utc>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub last_result_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    /// Set while a replica is running this schedule; the lock expires at this time
+    #[serde(default)]
+    pub running_until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScheduleRequest {
+    pub name: Option<String>,
+    pub request: CrawlRequest,
+    pub cron: Option<String>,
+    pub interval_seconds: Option<u64>,
+    pub enabled: Option<bool>,
+}
+
+// Helper function to parse a cron expression, accepting the classic 5-field form as well
+fn parse_cron(expression: &str) -> Result<CronSchedule, String> {
+    let expression = expression.trim();
+    let expression = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.to_string()
+    };
+
+    CronSchedule::from_str(&expression).map_err(|e| format!("Invalid cron expression: {}", e))
+}
+
+/// Compute the next run after `after` from either a cron expression or a fixed interval
+pub fn next_run_after(cron: Option<&str>, interval_seconds: Option<u64>, after: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    match (cron, interval_seconds) {
+        (Some(expression), None) => parse_cron(expression)?
+            .after(&after)
+            .next()
+            .ok_or_else(|| "Cron expression has no upcoming runs".to_string()),
+        (None, Some(seconds)) => {
+            if seconds < MIN_INTERVAL_SECONDS {
+                return Err(format!("interval_seconds must be at least {}", MIN_INTERVAL_SECONDS));
+            }
+            Ok(after + chrono::Duration::seconds(seconds as i64))
+        }
+        _ => Err("Provide exactly one of cron or interval_seconds".to_string()),
+    }
+}
+
+// Helper function to identify this replica when claiming schedule runs
+fn replica_id() -> String {
+    std::env::var("HOSTNAME").unwrap_or_else(|_| Uuid::new_v4().to_string())
+}
+
+// Helper function to compute until when a run lock started now is held
+fn lock_deadline(app: &AppState, now: DateTime<Utc>) -> DateTime<Utc> {
+    now + chrono::Duration::seconds(app.crawl_limits.max_time_seconds as i64 + LOCK_MARGIN_SECONDS)
+}
+
+/// Poll the schedule table and run due crawls. Every replica runs this loop; a lightweight
+/// transaction on `next_run_at` makes sure only one replica claims each run, and a schedule is
+/// not claimed again while its previous run still holds the lock.
+pub async fn run_scheduler(app: AppState) {
+    let state = &app.cassandra;
+    let owner = replica_id();
+    let poll_seconds = std::env::var("SCHEDULER_POLL_SECONDS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(30);
+    let mut ticker = interval(Duration::from_secs(poll_seconds));
+
+    println!("Scheduler running as {} (poll every {}s)", owner, poll_seconds);
+
+    loop {
+        ticker.tick().await;
+
+        let schedules = match list_schedules(&state.session, &state.keyspace).await {
+            Ok(schedules) => schedules,
+            Err(e) => {
+                eprintln!("[Scheduler] Failed to load schedules: {}", e);
+                continue;
+            }
+        };
+
+        let now = Utc::now();
+        for schedule in schedules.into_iter().filter(|s| s.enabled && s.next_run_at <= now) {
+            if schedule.running_until.is_some_and(|until| until > now) {
+                continue; // Previous run still in progress; the missed run starts once it ends
+            }
+
+            let next_run_at = match next_run_after(schedule.cron.as_deref(), schedule.interval_seconds, now) {
+                Ok(next) => next,
+                Err(e) => {
+                    eprintln!("[Scheduler] Schedule {} has an invalid cadence: {}", schedule.id, e);
+                    continue;
+                }
+            };
+
+            let locked_until = lock_deadline(&app, now);
+            match claim_schedule_run(&state.session, &state.keyspace, schedule.id, schedule.next_run_at, next_run_at, &owner, locked_until).await {
+                Ok(true) => {
+                    let app = app.clone();
+                    let owner = owner.clone();
+                    tokio::spawn(async move { run_schedule(app, schedule, owner).await });
+                }
+                Ok(false) => {} // Another replica claimed this run
+                Err(e) => eprintln!("[Scheduler] Failed to claim schedule {}: {}", schedule.id, e),
+            }
+        }
+    }
+}
+
+// Run one claimed schedule and record the outcome. Scheduled crawls share the crawler pool
+// with ad-hoc crawls but wait for a slot instead of being turned away; the run lock is renewed
+// once the slot is granted so the wait does not eat into it.
+async fn run_schedule(app: AppState, schedule: Schedule, owner: String) {
+    let _permit = app.crawl_admission.admit_waiting().await;
+    let state = &app.cassandra;
+    match extend_schedule_lock(&state.session, &state.keyspace, schedule.id, &owner, lock_deadline(&app, Utc::now())).await {
+        Ok(true) => {}
+        Ok(false) => {
+            eprintln!("[Scheduler] Lost the run lock of schedule {} while queued; skipping", schedule.id);
+            return;
+        }
+        Err(e) => eprintln!("[Scheduler] Failed to renew the run lock of schedule {}: {}", schedule.id, e),
+    }
+    println!("[Scheduler] Running schedule {}", schedule.id);
+    let started_at = Utc::now();
+
+    let (last_error, last_result_id) = match execute_crawl(&app, &schedule.request).await {
+        Ok((result_id, _)) => (None, Some(result_id)),
+        Err(e) => {
+            eprintln!("[Scheduler] Schedule {} failed: {}", schedule.id, e);
+            (Some(e.to_string()), None)
+        }
+    };
+
+    if let Err(e) = record_schedule_run(&state.session, &state.keyspace, schedule.id, &owner, started_at, last_error, last_result_id).await {
+        eprintln!("[Scheduler] Failed to record run of schedule {}: {}", schedule.id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 6, hour, minute, second).unwrap()
+    }
+
+    #[test]
+    fn parse_cron_accepts_five_and_six_fields() {
+        let five = parse_cron("*/15 * * * *").unwrap();
+        let six = parse_cron("0 */15 * * * *").unwrap();
+        assert_eq!(five.after(&at(10, 1, 0)).next(), Some(at(10, 15, 0)));
+        assert_eq!(five.after(&at(10, 1, 0)).next(), six.after(&at(10, 1, 0)).next());
+    }
+
+    #[test]
+    fn parse_cron_rejects_invalid_expressions() {
+        assert!(parse_cron("not a cron").is_err());
+        assert!(parse_cron("61 * * * *").is_err());
+        assert!(parse_cron("").is_err());
+    }
+
+    #[test]
+    fn next_run_after_follows_the_cron_expression() {
+        let next = next_run_after(Some("0 6 * * *"), None, at(10, 0, 0)).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2024, 5, 7, 6, 0, 0).unwrap());
+        // Strictly after: a run exactly on a boundary moves to the next one
+        let next = next_run_after(Some("0 * * * *"), None, at(11, 0, 0)).unwrap();
+        assert_eq!(next, at(12, 0, 0));
+    }
+
+    #[test]
+    fn next_run_after_adds_the_interval() {
+        let next = next_run_after(None, Some(3600), at(10, 30, 0)).unwrap();
+        assert_eq!(next, at(11, 30, 0));
+        assert!(next_run_after(None, Some(MIN_INTERVAL_SECONDS), at(10, 30, 0)).is_ok());
+    }
+
+    #[test]
+    fn next_run_after_rejects_short_intervals() {
+        let err = next_run_after(None, Some(MIN_INTERVAL_SECONDS - 1), at(10, 0, 0)).unwrap_err();
+        assert!(err.contains("at least"));
+    }
+
+    #[test]
+    fn next_run_after_requires_exactly_one_cadence() {
+        assert!(next_run_after(None, None, at(10, 0, 0)).is_err());
+        assert!(next_run_after(Some("0 * * * *"), Some(3600), at(10, 0, 0)).is_err());
+        assert!(next_run_after(Some("bogus"), None, at(10, 0, 0)).is_err());
+    }
+}
@@ -0,0 +1,267 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use tokio::time::{Duration, Instant};
+
+/// Breaker settings shared by every upstream host, read from the environment once
+#[derive(Debug, Clone)]
+pub struct BreakerConfig {
+    /// Consecutive failed calls that open the circuit
+    pub failure_threshold: u32,
+    /// How long an open circuit rejects calls before letting a probe through
+    pub open_for: Duration,
+}
+
+impl BreakerConfig {
+    pub fn from_env() -> Self {
+        let failure_threshold = std::env::var("SOCIAL_BREAKER_FAILURE_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(5);
+        let open_seconds = std::env::var("SOCIAL_BREAKER_OPEN_SECONDS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(30);
+
+        Self {
+            failure_threshold,
+            open_for: Duration::from_secs(open_seconds),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+struct Circuit {
+    state: CircuitState,
+    consecutive_failures: u32,
+    /// When the circuit last opened, or when the half-open probe was let through
+    since: Instant,
+    last_provider: String,
+}
+
+/// Current state of one upstream host, exposed on the admin endpoint
+#[derive(Debug, Serialize)]
+pub struct CircuitStatus {
+    pub host: String,
+    pub provider: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    /// Seconds until an open circuit lets a probe through
+    pub retry_in_seconds: Option<u64>,
+}
+
+/// Process-wide circuit breakers per upstream host. After `failure_threshold` consecutive
+/// failures a host's circuit opens and calls fail fast; once `open_for` has passed one probe
+/// call is let through (half-open), which closes the circuit on success or reopens it.
+pub struct CircuitBreakers {
+    config: BreakerConfig,
+    circuits: Mutex<HashMap<String, Circuit>>,
+}
+
+/// The shared circuit breakers
+pub fn circuit_breakers() -> &'static CircuitBreakers {
+    static BREAKERS: OnceLock<CircuitBreakers> = OnceLock::new();
+    BREAKERS.get_or_init(|| CircuitBreakers::new(BreakerConfig::from_env()))
+}
+
+impl CircuitBreakers {
+    pub fn new(config: BreakerConfig) -> Self {
+        Self {
+            config,
+            circuits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether a call to `host` may go ahead; `Err` carries how long the circuit stays open
+    pub fn allow(&self, host: &str, provider: &str) -> Result<(), Duration> {
+        let mut circuits = self.circuits.lock().unwrap();
+        let circuit = circuits.entry(host.to_string()).or_insert_with(|| Circuit {
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            since: Instant::now(),
+            last_provider: provider.to_string(),
+        });
+        circuit.last_provider = provider.to_string();
+
+        let elapsed = circuit.since.elapsed();
+        match circuit.state {
+            CircuitState::Closed => Ok(()),
+            // A probe that never reported back (e.g. the caller went away) frees up after `open_for`
+            CircuitState::Open | CircuitState::HalfOpen if elapsed >= self.config.open_for => {
+                circuit.state = CircuitState::HalfOpen;
+                circuit.since = Instant::now();
+                Ok(())
+            }
+            CircuitState::Open | CircuitState::HalfOpen => Err(self.config.open_for - elapsed),
+        }
+    }
+
+    pub fn record_success(&self, host: &str) {
+        if let Some(circuit) = self.circuits.lock().unwrap().get_mut(host) {
+            circuit.state = CircuitState::Closed;
+            circuit.consecutive_failures = 0;
+        }
+    }
+
+    pub fn record_failure(&self, host: &str) {
+        let mut circuits = self.circuits.lock().unwrap();
+        let Some(circuit) = circuits.get_mut(host) else {
+            return;
+        };
+        circuit.consecutive_failures += 1;
+        let reopen = circuit.state == CircuitState::HalfOpen;
+        if reopen || circuit.consecutive_failures >= self.config.failure_threshold {
+            if circuit.state != CircuitState::Open {
+                eprintln!("[Social] Circuit opened for {} after {} failures", host, circuit.consecutive_failures);
+            }
+            circuit.state = CircuitState::Open;
+            circuit.since = Instant::now();
+        }
+    }
+
+    /// State of every host called so far, open circuits first
+    pub fn snapshot(&self) -> Vec<CircuitStatus> {
+        let circuits = self.circuits.lock().unwrap();
+        let mut statuses: Vec<CircuitStatus> = circuits
+            .iter()
+            .map(|(host, circuit)| CircuitStatus {
+                host: host.clone(),
+                provider: circuit.last_provider.clone(),
+                state: circuit.state,
+                consecutive_failures: circuit.consecutive_failures,
+                retry_in_seconds: (circuit.state != CircuitState::Closed)
+                    .then(|| self.config.open_for.saturating_sub(circuit.since.elapsed()).as_secs()),
+            })
+            .collect();
+        statuses.sort_by_key(|status| (status.state == CircuitState::Closed, status.host.clone()));
+        statuses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPEN_FOR: Duration = Duration::from_millis(50);
+
+    fn breakers() -> CircuitBreakers {
+        CircuitBreakers::new(BreakerConfig {
+            failure_threshold: 3,
+            open_for: OPEN_FOR,
+        })
+    }
+
+    fn state(breakers: &CircuitBreakers, host: &str) -> CircuitState {
+        breakers.snapshot().into_iter().find(|status| status.host == host).unwrap().state
+    }
+
+    // Helper function to fail calls to `host` until its circuit opens
+    fn trip(breakers: &CircuitBreakers, host: &str) {
+        for _ in 0..3 {
+            breakers.allow(host, "tikhub_twitter").unwrap();
+            breakers.record_failure(host);
+        }
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breakers = breakers();
+        for _ in 0..2 {
+            assert!(breakers.allow("api.tikhub.io", "tikhub_twitter").is_ok());
+            breakers.record_failure("api.tikhub.io");
+        }
+        assert_eq!(state(&breakers, "api.tikhub.io"), CircuitState::Closed);
+
+        breakers.record_failure("api.tikhub.io");
+        assert_eq!(state(&breakers, "api.tikhub.io"), CircuitState::Open);
+        let retry_in = breakers.allow("api.tikhub.io", "tikhub_twitter").unwrap_err();
+        assert!(retry_in <= OPEN_FOR);
+    }
+
+    #[test]
+    fn a_success_resets_the_failure_count() {
+        let breakers = breakers();
+        breakers.allow("api.tikhub.io", "tikhub_twitter").unwrap();
+        breakers.record_failure("api.tikhub.io");
+        breakers.record_failure("api.tikhub.io");
+        breakers.record_success("api.tikhub.io");
+        breakers.record_failure("api.tikhub.io");
+
+        assert_eq!(state(&breakers, "api.tikhub.io"), CircuitState::Closed);
+        assert!(breakers.allow("api.tikhub.io", "tikhub_twitter").is_ok());
+    }
+
+    #[test]
+    fn lets_one_probe_through_once_open_for_has_passed() {
+        let breakers = breakers();
+        trip(&breakers, "api.tikhub.io");
+        std::thread::sleep(OPEN_FOR);
+
+        assert!(breakers.allow("api.tikhub.io", "tikhub_twitter").is_ok());
+        assert_eq!(state(&breakers, "api.tikhub.io"), CircuitState::HalfOpen);
+        // Other calls keep failing fast while the probe is out
+        assert!(breakers.allow("api.tikhub.io", "tikhub_twitter").is_err());
+    }
+
+    #[test]
+    fn a_successful_probe_closes_the_circuit() {
+        let breakers = breakers();
+        trip(&breakers, "api.tikhub.io");
+        std::thread::sleep(OPEN_FOR);
+        breakers.allow("api.tikhub.io", "tikhub_twitter").unwrap();
+        breakers.record_success("api.tikhub.io");
+
+        assert_eq!(state(&breakers, "api.tikhub.io"), CircuitState::Closed);
+        assert!(breakers.allow("api.tikhub.io", "tikhub_twitter").is_ok());
+        // The count starts over, so one more failure does not reopen it
+        breakers.record_failure("api.tikhub.io");
+        assert_eq!(state(&breakers, "api.tikhub.io"), CircuitState::Closed);
+    }
+
+    #[test]
+    fn a_failed_probe_reopens_the_circuit() {
+        let breakers = breakers();
+        trip(&breakers, "api.tikhub.io");
+        std::thread::sleep(OPEN_FOR);
+        breakers.allow("api.tikhub.io", "tikhub_twitter").unwrap();
+        breakers.record_failure("api.tikhub.io");
+
+        assert_eq!(state(&breakers, "api.tikhub.io"), CircuitState::Open);
+        assert!(breakers.allow("api.tikhub.io", "tikhub_twitter").is_err());
+    }
+
+    #[test]
+    fn an_abandoned_probe_frees_up_after_open_for() {
+        let breakers = breakers();
+        trip(&breakers, "api.tikhub.io");
+        std::thread::sleep(OPEN_FOR);
+        breakers.allow("api.tikhub.io", "tikhub_twitter").unwrap();
+        std::thread::sleep(OPEN_FOR);
+
+        assert!(breakers.allow("api.tikhub.io", "tikhub_twitter").is_ok());
+        assert_eq!(state(&breakers, "api.tikhub.io"), CircuitState::HalfOpen);
+    }
+
+    #[test]
+    fn circuits_are_kept_per_host_and_open_ones_listed_first() {
+        let breakers = breakers();
+        breakers.allow("api.tikhub.io", "tikhub_twitter").unwrap();
+        trip(&breakers, "twitter-v24.p.rapidapi.com");
+
+        let snapshot = breakers.snapshot();
+        assert_eq!(snapshot[0].host, "twitter-v24.p.rapidapi.com");
+        assert_eq!(snapshot[0].state, CircuitState::Open);
+        assert!(snapshot[0].retry_in_seconds.is_some());
+        assert_eq!(snapshot[1].host, "api.tikhub.io");
+        assert_eq!(snapshot[1].retry_in_seconds, None);
+    }
+}
@@ -0,0 +1,274 @@
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::{upstream_from, ProxyRequest, SocialProvider, UpstreamBody, UpstreamResponse};
+use crate::db::{find_cached_social_result, CassandraState};
+
+struct CacheEntry {
+    response: UpstreamResponse,
+    expires_at: Instant,
+    last_used: u64,
+}
+
+struct MemoryTier {
+    entries: HashMap<String, CacheEntry>,
+    tick: u64,
+}
+
+/// Cache of successful upstream responses, keyed by provider, method, path and params.
+/// The in-memory tier is an LRU per replica; with `SOCIAL_CACHE_CASSANDRA=true` a miss also
+/// looks for a fresh enough payload in `social_results`, which other replicas write too.
+pub struct ResponseCache {
+    default_ttl: Duration,
+    ttls: HashMap<String, Duration>,
+    capacity: usize,
+    memory: Mutex<MemoryTier>,
+    cassandra: Option<CassandraState>,
+}
+
+impl ResponseCache {
+    pub fn from_env(cassandra: &CassandraState) -> Self {
+        let default_ttl = std::env::var("SOCIAL_CACHE_TTL_SECONDS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(300);
+        // Per-provider overrides, e.g. {"tikhub_twitter": 60, "rapidapi_instagram": 0}
+        let ttls: HashMap<String, u64> = crate::config::env_json("SOCIAL_CACHE_TTLS").unwrap_or_default();
+        let capacity = std::env::var("SOCIAL_CACHE_CAPACITY")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(1000);
+        let use_cassandra = std::env::var("SOCIAL_CACHE_CASSANDRA")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+
+        Self {
+            default_ttl: Duration::from_secs(default_ttl),
+            ttls: ttls.into_iter().map(|(source, secs)| (source, Duration::from_secs(secs))).collect(),
+            capacity,
+            memory: Mutex::new(MemoryTier {
+                entries: HashMap::new(),
+                tick: 0,
+            }),
+            cassandra: use_cassandra.then(|| cassandra.clone()),
+        }
+    }
+
+    /// How long a provider's responses stay fresh; `None` when caching is off for it
+    pub fn ttl(&self, source: &str) -> Option<Duration> {
+        let ttl = self.ttls.get(source).copied().unwrap_or(self.default_ttl);
+        (!ttl.is_zero() && self.capacity > 0).then_some(ttl)
+    }
+
+    /// Cache key for a request; params are canonicalized so key order does not matter
+    pub fn key(provider: &dyn SocialProvider, request: &ProxyRequest) -> String {
+        let params = request.params_for(provider.post_layout()).map(canonical).unwrap_or(Value::Null);
+        let material = format!(
+            "{}\n{}\n{}\n{}",
+            provider.source(),
+            request.method().to_ascii_uppercase(),
+            request.path.trim_matches('/'),
+            params
+        );
+        hex::encode(Sha256::digest(material.as_bytes()))
+    }
+
+    pub async fn get(&self, provider: &dyn SocialProvider, key: &str, ttl: Duration) -> Option<UpstreamResponse> {
+        {
+            let mut memory = self.memory.lock().unwrap();
+            memory.tick += 1;
+            let tick = memory.tick;
+            match memory.entries.get_mut(key) {
+                Some(entry) if entry.expires_at > Instant::now() => {
+                    entry.last_used = tick;
+                    let mut response = entry.response.clone();
+                    response.cached = true;
+                    return Some(response);
+                }
+                Some(_) => {
+                    memory.entries.remove(key);
+                }
+                None => {}
+            }
+        }
+
+        let cassandra = self.cassandra.as_ref()?;
+        let (payload, status, stored_at) =
+            match find_cached_social_result(&cassandra.session, &cassandra.keyspace, key).await {
+                Ok(found) => found?,
+                Err(e) => {
+                    eprintln!("[Cassandra] Cache lookup failed: {}", e);
+                    return None;
+                }
+            };
+        let age = (chrono::Utc::now() - stored_at).to_std().unwrap_or_default();
+        if age >= ttl || !(200..300).contains(&status) {
+            return None;
+        }
+
+        let data = serde_json::from_str::<Value>(&payload).ok()?;
+        let mut response = upstream_from(provider, status as u16, UpstreamBody::Json(Some(data)));
+        response.cache_key = Some(key.to_string());
+        self.put(key, &response, ttl - age);
+        response.cached = true;
+        Some(response)
+    }
+
+    /// Remember a successful response; anything else is not cached
+    pub fn put(&self, key: &str, response: &UpstreamResponse, ttl: Duration) {
+        if !(200..300).contains(&response.status) {
+            return;
+        }
+        let mut memory = self.memory.lock().unwrap();
+        memory.tick += 1;
+        let tick = memory.tick;
+        if !memory.entries.contains_key(key) && memory.entries.len() >= self.capacity {
+            let oldest = memory
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                memory.entries.remove(&oldest);
+            }
+        }
+        memory.entries.insert(
+            key.to_string(),
+            CacheEntry {
+                response: response.clone(),
+                expires_at: Instant::now() + ttl,
+                last_used: tick,
+            },
+        );
+    }
+}
+
+// Helper function to sort object keys recursively
+fn canonical(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(entries.into_iter().map(|(k, v)| (k, canonical(v))).collect())
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(canonical).collect()),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::social::RapidApiProvider;
+    use serde_json::json;
+
+    fn cache(capacity: usize) -> ResponseCache {
+        ResponseCache {
+            default_ttl: Duration::from_secs(300),
+            ttls: HashMap::from([("rapidapi_instagram".to_string(), Duration::ZERO)]),
+            capacity,
+            memory: Mutex::new(MemoryTier {
+                entries: HashMap::new(),
+                tick: 0,
+            }),
+            cassandra: None,
+        }
+    }
+
+    fn provider() -> RapidApiProvider {
+        RapidApiProvider::new("rapidapi_twitter_v24", "twitter-v24.p.rapidapi.com")
+    }
+
+    fn response(status: u16, text: &str) -> UpstreamResponse {
+        upstream_from(&provider(), status, UpstreamBody::Text(Some(text.to_string())))
+    }
+
+    fn text(response: &UpstreamResponse) -> &str {
+        match &response.body {
+            UpstreamBody::Text(Some(text)) => text,
+            other => panic!("unexpected body {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn fresh_entries_are_served_as_cached() {
+        let cache = cache(10);
+        cache.put("a", &response(200, "first"), Duration::from_secs(60));
+
+        let hit = cache.get(&provider(), "a", Duration::from_secs(60)).await.unwrap();
+        assert!(hit.cached);
+        assert_eq!(text(&hit), "first");
+        assert!(cache.get(&provider(), "b", Duration::from_secs(60)).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn expired_entries_are_dropped() {
+        let cache = cache(10);
+        cache.put("a", &response(200, "first"), Duration::from_millis(20));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert!(cache.get(&provider(), "a", Duration::from_millis(20)).await.is_none());
+        assert!(cache.memory.lock().unwrap().entries.is_empty());
+    }
+
+    #[tokio::test]
+    async fn the_least_recently_used_entry_is_evicted_at_capacity() {
+        let cache = cache(2);
+        let ttl = Duration::from_secs(60);
+        cache.put("a", &response(200, "a"), ttl);
+        cache.put("b", &response(200, "b"), ttl);
+        // Reading `a` makes `b` the least recently used
+        assert!(cache.get(&provider(), "a", ttl).await.is_some());
+        cache.put("c", &response(200, "c"), ttl);
+
+        assert!(cache.get(&provider(), "a", ttl).await.is_some());
+        assert!(cache.get(&provider(), "b", ttl).await.is_none());
+        assert!(cache.get(&provider(), "c", ttl).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn replacing_an_entry_does_not_evict_another() {
+        let cache = cache(2);
+        let ttl = Duration::from_secs(60);
+        cache.put("a", &response(200, "a"), ttl);
+        cache.put("b", &response(200, "b"), ttl);
+        cache.put("a", &response(200, "newer"), ttl);
+
+        assert_eq!(text(&cache.get(&provider(), "a", ttl).await.unwrap()), "newer");
+        assert!(cache.get(&provider(), "b", ttl).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn unsuccessful_responses_are_not_cached() {
+        let cache = cache(10);
+        cache.put("a", &response(429, "slow down"), Duration::from_secs(60));
+        cache.put("b", &response(500, "oops"), Duration::from_secs(60));
+
+        assert!(cache.memory.lock().unwrap().entries.is_empty());
+    }
+
+    #[test]
+    fn ttl_honours_overrides_and_disabling() {
+        assert_eq!(cache(10).ttl("tikhub_twitter"), Some(Duration::from_secs(300)));
+        assert_eq!(cache(10).ttl("rapidapi_instagram"), None);
+        assert_eq!(cache(0).ttl("tikhub_twitter"), None);
+    }
+
+    #[test]
+    fn key_ignores_param_order() {
+        let request = |params| ProxyRequest {
+            path: "search/".to_string(),
+            params: Some(params),
+            ..Default::default()
+        };
+        let first = ResponseCache::key(&provider(), &request(json!({"query": "rust", "count": 20})));
+        let second = ResponseCache::key(&provider(), &request(json!({"count": 20, "query": "rust"})));
+        let other = ResponseCache::key(&provider(), &request(json!({"query": "go", "count": 20})));
+
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+    }
+}
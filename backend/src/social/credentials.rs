@@ -0,0 +1,417 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use tokio::time::{Duration, Instant};
+
+use crate::config::{ConfigError, CredentialEntry, SocialCredentials};
+
+/// How the next key is picked from a pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialStrategy {
+    RoundRobin,
+    LeastUsed,
+}
+
+/// Rotation and cooldown settings shared by every pool, read from the environment once
+#[derive(Debug, Clone)]
+pub struct CredentialPolicy {
+    pub strategy: CredentialStrategy,
+    /// How long a key sits out after a `429` that gave no `Retry-After`
+    pub rate_limit_cooldown: Duration,
+    /// How long a key sits out after the upstream rejected it with `401`/`403`
+    pub auth_cooldown: Duration,
+}
+
+impl CredentialPolicy {
+    pub fn from_env() -> Self {
+        let strategy = match std::env::var("SOCIAL_CREDENTIAL_STRATEGY").as_deref() {
+            Ok("least_used") => CredentialStrategy::LeastUsed,
+            _ => CredentialStrategy::RoundRobin,
+        };
+        let seconds = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(Duration::from_secs(default))
+        };
+
+        Self {
+            strategy,
+            rate_limit_cooldown: seconds("SOCIAL_CREDENTIAL_COOLDOWN_SECONDS", 30),
+            auth_cooldown: seconds("SOCIAL_CREDENTIAL_AUTH_COOLDOWN_SECONDS", 600),
+        }
+    }
+}
+
+/// A key checked out of a pool for one upstream call
+#[derive(Debug, Clone)]
+pub struct Credential {
+    pub pool: &'static str,
+    pub secret: String,
+    index: usize,
+}
+
+/// Why a pool could not hand out a key
+#[derive(Debug)]
+pub enum CheckoutError {
+    /// The pool has no keys; carries the env var that configures them
+    Missing(&'static str),
+    /// Every key is cooling down; carries the time until the first is usable again
+    CoolingDown(Duration),
+}
+
+struct KeySlot {
+    label: String,
+    secret: String,
+    requests: u64,
+    successes: u64,
+    rejected: u64,
+    rate_limited: u64,
+    last_used_at: Option<DateTime<Utc>>,
+    cooldown_until: Option<Instant>,
+}
+
+struct Pool {
+    env_name: &'static str,
+    keys: Vec<KeySlot>,
+    next: usize,
+}
+
+/// Usage of one key, exposed on the admin endpoint. The key itself is never shown.
+#[derive(Debug, Serialize)]
+pub struct CredentialUsage {
+    pub pool: String,
+    pub label: String,
+    pub requests: u64,
+    pub successes: u64,
+    /// Calls the upstream answered with `401`/`403`
+    pub rejected: u64,
+    /// Calls the upstream answered with `429`
+    pub rate_limited: u64,
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Seconds until a cooling key is picked again
+    pub cooldown_seconds: Option<u64>,
+}
+
+/// Process-wide pools of upstream API keys, one per provider kind (`tikhub`, `rapidapi`). Keys
+/// are rotated per call; a key the upstream rejects or rate limits cools down while the others
+/// take over.
+pub struct CredentialPools {
+    policy: CredentialPolicy,
+    pools: Mutex<HashMap<&'static str, Pool>>,
+}
+
+static POOLS: OnceLock<CredentialPools> = OnceLock::new();
+
+/// Load the shared credential pools at startup, refusing an invalid `SOCIAL_CREDENTIALS`
+pub fn init_credential_pools() -> Result<(), ConfigError> {
+    let credentials = SocialCredentials::from_env()?;
+    let _ = POOLS.set(CredentialPools::new(CredentialPolicy::from_env(), credentials));
+    Ok(())
+}
+
+/// The shared credential pools, loaded by `init_credential_pools`
+pub fn credential_pools() -> &'static CredentialPools {
+    POOLS.get_or_init(|| {
+        let credentials = SocialCredentials::from_env().unwrap_or_default();
+        CredentialPools::new(CredentialPolicy::from_env(), credentials)
+    })
+}
+
+impl CredentialPools {
+    pub fn new(policy: CredentialPolicy, credentials: SocialCredentials) -> Self {
+        let pool = |env_name: &'static str, entries: Vec<CredentialEntry>| Pool {
+            env_name,
+            keys: entries
+                .into_iter()
+                .map(|entry| KeySlot {
+                    label: entry.label.unwrap_or_else(|| masked(&entry.key)),
+                    secret: entry.key,
+                    requests: 0,
+                    successes: 0,
+                    rejected: 0,
+                    rate_limited: 0,
+                    last_used_at: None,
+                    cooldown_until: None,
+                })
+                .collect(),
+            next: 0,
+        };
+
+        let mut pools = HashMap::new();
+        pools.insert("tikhub", pool("TIKHUB_TOKEN", credentials.tikhub));
+        pools.insert("rapidapi", pool("RAPIDAPI_KEY", credentials.rapidapi));
+        Self {
+            policy,
+            pools: Mutex::new(pools),
+        }
+    }
+
+    /// Pick a key that is not cooling down and count the call against it
+    pub fn checkout(&self, pool_name: &'static str) -> Result<Credential, CheckoutError> {
+        let mut pools = self.pools.lock().unwrap();
+        let Some(pool) = pools.get_mut(pool_name) else {
+            return Err(CheckoutError::Missing(pool_name));
+        };
+        if pool.keys.is_empty() {
+            return Err(CheckoutError::Missing(pool.env_name));
+        }
+
+        let now = Instant::now();
+        let available = |slot: &KeySlot| slot.cooldown_until.is_none_or(|until| until <= now);
+        let count = pool.keys.len();
+        let index = match self.policy.strategy {
+            CredentialStrategy::RoundRobin => (0..count)
+                .map(|offset| (pool.next + offset) % count)
+                .find(|index| available(&pool.keys[*index])),
+            CredentialStrategy::LeastUsed => (0..count)
+                .filter(|index| available(&pool.keys[*index]))
+                .min_by_key(|index| pool.keys[*index].requests),
+        };
+        let Some(index) = index else {
+            let wait = pool
+                .keys
+                .iter()
+                .filter_map(|slot| slot.cooldown_until)
+                .min()
+                .map(|until| until - now)
+                .unwrap_or_default();
+            return Err(CheckoutError::CoolingDown(wait));
+        };
+
+        pool.next = (index + 1) % count;
+        let slot = &mut pool.keys[index];
+        slot.requests += 1;
+        slot.last_used_at = Some(Utc::now());
+        Ok(Credential {
+            pool: pool_name,
+            secret: slot.secret.clone(),
+            index,
+        })
+    }
+
+    /// Count the upstream's answer to a call made with `credential`. A `401`/`403` cools the key
+    /// down for `auth_cooldown`, a `429` for `retry_after` or else `rate_limit_cooldown`.
+    pub fn record(&self, credential: &Credential, status: u16, retry_after: Option<Duration>) {
+        let cooldown = match status {
+            401 | 403 => Some(self.policy.auth_cooldown),
+            429 => Some(retry_after.unwrap_or(self.policy.rate_limit_cooldown)),
+            _ => None,
+        };
+        self.with_slot(credential, |slot, pool_name| {
+            match status {
+                200..=299 => slot.successes += 1,
+                401 | 403 => {
+                    slot.rejected += 1;
+                    eprintln!("[Social] {} key {} rejected with {}, cooling down", pool_name, slot.label, status);
+                }
+                429 => slot.rate_limited += 1,
+                _ => {}
+            }
+            if let Some(cooldown) = cooldown {
+                extend_cooldown(slot, cooldown);
+            }
+        });
+    }
+
+    /// Keep a key out of rotation for `duration`, e.g. until its exhausted quota resets
+    pub fn cool_down(&self, credential: &Credential, duration: Duration) {
+        self.with_slot(credential, |slot, _| extend_cooldown(slot, duration));
+    }
+
+    /// Whether the pool has a key that may be used right now
+    pub fn has_available(&self, pool_name: &str) -> bool {
+        let pools = self.pools.lock().unwrap();
+        let now = Instant::now();
+        pools.get(pool_name).is_some_and(|pool| {
+            pool.keys
+                .iter()
+                .any(|slot| slot.cooldown_until.is_none_or(|until| until <= now))
+        })
+    }
+
+    /// Usage of every key, by pool and then in configuration order
+    pub fn snapshot(&self) -> Vec<CredentialUsage> {
+        let pools = self.pools.lock().unwrap();
+        let now = Instant::now();
+        let mut names: Vec<&&'static str> = pools.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .flat_map(|name| {
+                pools[*name].keys.iter().map(move |slot| CredentialUsage {
+                    pool: name.to_string(),
+                    label: slot.label.clone(),
+                    requests: slot.requests,
+                    successes: slot.successes,
+                    rejected: slot.rejected,
+                    rate_limited: slot.rate_limited,
+                    last_used_at: slot.last_used_at,
+                    cooldown_seconds: slot
+                        .cooldown_until
+                        .filter(|until| *until > now)
+                        .map(|until| (until - now).as_secs().max(1)),
+                })
+            })
+            .collect()
+    }
+
+    fn with_slot(&self, credential: &Credential, update: impl FnOnce(&mut KeySlot, &str)) {
+        let mut pools = self.pools.lock().unwrap();
+        if let Some(slot) = pools.get_mut(credential.pool).and_then(|pool| pool.keys.get_mut(credential.index)) {
+            update(slot, credential.pool);
+        }
+    }
+}
+
+// Helper function to push a key's cooldown out, never pulling it in
+fn extend_cooldown(slot: &mut KeySlot, duration: Duration) {
+    let until = Instant::now() + duration;
+    slot.cooldown_until = Some(slot.cooldown_until.map_or(until, |current| current.max(until)));
+}
+
+// Helper function to label a key by its last four characters
+fn masked(key: &str) -> String {
+    let tail: String = key.chars().rev().take(4).collect::<Vec<_>>().into_iter().rev().collect();
+    format!("...{}", tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: &str) -> CredentialEntry {
+        CredentialEntry {
+            key: key.to_string(),
+            label: None,
+        }
+    }
+
+    fn pools(strategy: CredentialStrategy, tikhub: &[&str]) -> CredentialPools {
+        let policy = CredentialPolicy {
+            strategy,
+            rate_limit_cooldown: Duration::from_secs(30),
+            auth_cooldown: Duration::from_secs(600),
+        };
+        let credentials = SocialCredentials {
+            tikhub: tikhub.iter().map(|key| entry(key)).collect(),
+            rapidapi: Vec::new(),
+        };
+        CredentialPools::new(policy, credentials)
+    }
+
+    fn checkout(pools: &CredentialPools) -> Credential {
+        pools.checkout("tikhub").unwrap()
+    }
+
+    #[test]
+    fn round_robin_rotates_through_the_keys() {
+        let pools = pools(CredentialStrategy::RoundRobin, &["key-a", "key-b", "key-c"]);
+        let secrets: Vec<String> = (0..4).map(|_| checkout(&pools).secret).collect();
+        assert_eq!(secrets, ["key-a", "key-b", "key-c", "key-a"]);
+    }
+
+    #[test]
+    fn least_used_picks_the_key_with_fewest_calls() {
+        let pools = pools(CredentialStrategy::LeastUsed, &["key-a", "key-b", "key-c"]);
+        let first = checkout(&pools);
+        pools.record(&first, 429, Some(Duration::from_millis(50)));
+        let secrets: Vec<String> = (0..3).map(|_| checkout(&pools).secret).collect();
+        assert_eq!(secrets, ["key-b", "key-c", "key-b"]);
+
+        // Round robin would go on with key-c; key-a has made fewer calls once it is back
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(checkout(&pools).secret, "key-a");
+    }
+
+    #[test]
+    fn a_rejected_key_cools_down_while_the_others_take_over() {
+        let pools = pools(CredentialStrategy::RoundRobin, &["key-a", "key-b"]);
+        let rejected = checkout(&pools);
+        pools.record(&rejected, 403, None);
+
+        assert_eq!(checkout(&pools).secret, "key-b");
+        assert_eq!(checkout(&pools).secret, "key-b");
+        let usage = pools.snapshot();
+        assert_eq!(usage[0].rejected, 1);
+        assert!(usage[0].cooldown_seconds.is_some_and(|seconds| seconds > 500));
+        assert_eq!(usage[1].cooldown_seconds, None);
+    }
+
+    #[test]
+    fn a_rate_limited_key_returns_after_retry_after() {
+        let pools = pools(CredentialStrategy::RoundRobin, &["key-a", "key-b"]);
+        let limited = checkout(&pools);
+        pools.record(&limited, 429, Some(Duration::from_millis(50)));
+        assert_eq!(checkout(&pools).secret, "key-b");
+        assert_eq!(checkout(&pools).secret, "key-b");
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(checkout(&pools).secret, "key-a");
+        assert_eq!(pools.snapshot()[0].rate_limited, 1);
+    }
+
+    #[test]
+    fn a_rate_limit_without_retry_after_uses_the_default_cooldown() {
+        let pools = pools(CredentialStrategy::RoundRobin, &["key-a"]);
+        let limited = checkout(&pools);
+        pools.record(&limited, 429, None);
+
+        let cooldown = pools.snapshot()[0].cooldown_seconds.unwrap();
+        assert!((29..=30).contains(&cooldown));
+    }
+
+    #[test]
+    fn successes_do_not_cool_a_key_down() {
+        let pools = pools(CredentialStrategy::RoundRobin, &["key-a"]);
+        let credential = checkout(&pools);
+        pools.record(&credential, 200, None);
+        pools.record(&credential, 500, None);
+
+        assert_eq!(checkout(&pools).secret, "key-a");
+        let usage = pools.snapshot();
+        assert_eq!((usage[0].requests, usage[0].successes), (2, 1));
+        assert_eq!(usage[0].cooldown_seconds, None);
+    }
+
+    #[test]
+    fn a_pool_with_every_key_cooling_down_reports_the_shortest_wait() {
+        let pools = pools(CredentialStrategy::RoundRobin, &["key-a", "key-b"]);
+        let first = checkout(&pools);
+        let second = checkout(&pools);
+        pools.record(&first, 429, Some(Duration::from_secs(20)));
+        pools.record(&second, 429, Some(Duration::from_secs(5)));
+
+        assert!(!pools.has_available("tikhub"));
+        match pools.checkout("tikhub") {
+            Err(CheckoutError::CoolingDown(wait)) => assert!(wait <= Duration::from_secs(5) && wait > Duration::from_secs(4)),
+            other => panic!("unexpected checkout {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cooldowns_are_only_ever_extended() {
+        let pools = pools(CredentialStrategy::RoundRobin, &["key-a"]);
+        let credential = checkout(&pools);
+        pools.cool_down(&credential, Duration::from_secs(120));
+        pools.record(&credential, 429, Some(Duration::from_secs(1)));
+
+        assert!(pools.snapshot()[0].cooldown_seconds.is_some_and(|seconds| seconds > 100));
+    }
+
+    #[test]
+    fn an_empty_pool_names_its_env_var() {
+        let pools = pools(CredentialStrategy::RoundRobin, &[]);
+        assert!(matches!(pools.checkout("tikhub"), Err(CheckoutError::Missing("TIKHUB_TOKEN"))));
+        assert!(matches!(pools.checkout("rapidapi"), Err(CheckoutError::Missing("RAPIDAPI_KEY"))));
+        assert!(!pools.has_available("tikhub"));
+    }
+
+    #[test]
+    fn keys_are_labelled_without_revealing_them() {
+        let pools = pools(CredentialStrategy::RoundRobin, &["secret-token-1234"]);
+        assert_eq!(pools.snapshot()[0].label, "...1234");
+    }
+}
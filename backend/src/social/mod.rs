@@ -0,0 +1,605 @@
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::time::{sleep, timeout_at, Duration, Instant};
+
+use crate::config::SocialPagingLimits;
+use credentials::CheckoutError;
+use upstream::upstream_limiter;
+
+mod breaker;
+mod cache;
+mod credentials;
+mod posts;
+mod rapidapi;
+mod search;
+mod tikhub;
+mod upstream;
+
+pub use breaker::circuit_breakers;
+pub use cache::ResponseCache;
+pub use credentials::{credential_pools, init_credential_pools};
+pub use posts::{PostLayout, SocialPost};
+pub use rapidapi::RapidApiProvider;
+pub use search::{search_request, SEARCH_PLATFORMS};
+pub use tikhub::{TikHubProvider, TikHubQuery};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProviderError {
+    #[error("Missing {0} in environment")]
+    MissingCredential(String),
+
+    #[error("Request failed: {0}")]
+    Request(#[from] reqwest::Error),
+
+    #[error("Upstream request timed out")]
+    Timeout,
+
+    #[error("Path {0} leaves the provider's API")]
+    InvalidPath(String),
+
+    #[error("Upstream rate limit exhausted, retry in {}s", .0.as_secs())]
+    RateLimited(Duration),
+
+    #[error("Every {pool} key is cooling down, retry in {}s", .retry_in.as_secs())]
+    CredentialsCoolingDown { pool: String, retry_in: Duration },
+
+    #[error("Provider {provider} ({host}) is unavailable, retry in {}s", .retry_in.as_secs())]
+    CircuitOpen {
+        provider: String,
+        host: String,
+        retry_in: Duration,
+    },
+}
+
+/// Body of the social proxy endpoints
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProxyRequest {
+    pub path: String,
+    #[serde(default)]
+    pub params: Option<Value>,
+    #[serde(default)]
+    pub method: Option<String>,
+    /// Resume from a `next_cursor` returned by an earlier call
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Follow cursors until this many posts are collected
+    #[serde(default)]
+    pub max_items: Option<usize>,
+    /// Follow cursors for at most this many pages
+    #[serde(default)]
+    pub max_pages: Option<usize>,
+    /// Stream the upstream body back as-is, with its status and content type
+    #[serde(default)]
+    pub raw: bool,
+    /// The POST is read-only upstream, so a timeout or `5xx` may be retried like a GET
+    #[serde(default)]
+    pub retry_safe: bool,
+}
+
+impl ProxyRequest {
+    pub fn method(&self) -> String {
+        self.method.clone().unwrap_or_else(|| "GET".to_string())
+    }
+
+    pub fn is_get(&self) -> bool {
+        self.method().eq_ignore_ascii_case("GET")
+    }
+
+    /// Whether sending the request again cannot repeat a side effect: GETs, and POSTs the caller
+    /// marked `retry_safe`. Others are only resent when the upstream surely did not act on them.
+    pub fn is_retry_safe(&self) -> bool {
+        self.is_get() || self.retry_safe
+    }
+
+    /// Whether the caller asked for more than one page
+    pub fn paginates(&self) -> bool {
+        self.max_items.is_some() || self.max_pages.is_some()
+    }
+
+    // Helper function to put the cursor into the params under the provider's cursor param
+    fn params_for(&self, layout: Option<PostLayout>) -> Option<Value> {
+        let (Some(layout), Some(cursor)) = (layout, &self.cursor) else {
+            return self.params.clone();
+        };
+        let mut params = match &self.params {
+            Some(Value::Object(map)) => map.clone(),
+            _ => serde_json::Map::new(),
+        };
+        params.insert(layout.cursor_param().to_string(), Value::String(cursor.clone()));
+        Some(Value::Object(params))
+    }
+}
+
+/// What came back from the upstream API. `None` bodies could not be read.
+#[derive(Debug, Clone)]
+pub enum UpstreamBody {
+    Json(Option<Value>),
+    Text(Option<String>),
+}
+
+#[derive(Debug, Clone)]
+pub struct UpstreamResponse {
+    pub status: u16,
+    pub body: UpstreamBody,
+    /// Posts mapped from a JSON body, for providers with a known layout
+    pub posts: Option<Vec<SocialPost>>,
+    /// Cursor for the following page, for providers with a known layout
+    pub next_cursor: Option<String>,
+    /// Set when the response may be cached and reused
+    pub cache_key: Option<String>,
+    /// Served from the response cache rather than the upstream
+    pub cached: bool,
+    /// How long the upstream asked us to wait, when it rate limited us
+    pub retry_after: Option<Duration>,
+}
+
+/// Several upstream pages fetched by following cursors
+#[derive(Debug, Clone)]
+pub struct PagedResponse {
+    /// Status of the last page fetched
+    pub status: u16,
+    /// Each page as fetched; their posts are moved into `posts`
+    pub pages: Vec<UpstreamResponse>,
+    /// Posts from all pages, de-duplicated
+    pub posts: Vec<SocialPost>,
+    /// Where to resume; `None` once the upstream has no more pages
+    pub next_cursor: Option<String>,
+}
+
+impl PagedResponse {
+    /// Whether every page came from the response cache
+    pub fn cached(&self) -> bool {
+        !self.pages.is_empty() && self.pages.iter().all(|page| page.cached)
+    }
+}
+
+/// A social data API we proxy. Adding a provider is one impl plus a registry entry.
+pub trait SocialProvider: Send + Sync {
+    /// Label stored as `source` in `social_results`
+    fn source(&self) -> String;
+
+    /// Base URL that request paths are appended to, ending in `/`
+    fn base_url(&self) -> String;
+
+    /// TikHub service or RapidAPI host, as named in the social proxy allowlist
+    fn target(&self) -> String;
+
+    /// Credential pool the provider's API keys are drawn from (`tikhub` or `rapidapi`)
+    fn credential_pool(&self) -> &'static str;
+
+    /// Attach a key from the provider's credential pool
+    fn authorize(&self, request: RequestBuilder, credential: &str) -> RequestBuilder;
+
+    /// Turn request params into the query string for GET requests
+    fn query(&self, params: &Option<Value>) -> Vec<(String, String)> {
+        params_to_query(params)
+    }
+
+    /// Response layout to map into `SocialPost`s, if this provider has one
+    fn post_layout(&self) -> Option<PostLayout> {
+        None
+    }
+}
+
+// Helper function to render a JSON param value as a query string value
+pub(crate) fn param_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        _ => value.to_string(),
+    }
+}
+
+/// Flatten a JSON object of params into query pairs
+pub fn params_to_query(params: &Option<Value>) -> Vec<(String, String)> {
+    let mut query = Vec::new();
+    if let Some(Value::Object(map)) = params {
+        for (k, v) in map.iter() {
+            query.push((k.clone(), param_value(v)));
+        }
+    }
+    query
+}
+
+/// Full upstream URL for a request path, or `None` when the path would leave the provider's base
+/// URL: dot segments (also percent-encoded or with backslashes) are rejected, and the joined URL
+/// must keep the base's origin and path prefix
+pub fn upstream_url(provider: &dyn SocialProvider, path: &str) -> Option<String> {
+    let path = path.trim_start_matches('/');
+    let has_dot_segment = path
+        .split(['/', '\\'])
+        .map(|segment| segment.to_ascii_lowercase().replace("%2e", "."))
+        .any(|segment| segment == "." || segment == "..");
+    if has_dot_segment {
+        return None;
+    }
+
+    let base = url::Url::parse(&provider.base_url()).ok()?;
+    let url = base.join(path).ok()?;
+    (url.origin() == base.origin() && url.path().starts_with(base.path())).then(|| url.to_string())
+}
+
+/// Send a proxy request to a provider: GET with shaped query params, anything else as a JSON POST.
+/// Calls go through the upstream host's circuit breaker: after repeated timeouts, connection
+/// failures or 5xxs they fail fast with `CircuitOpen` until a probe call succeeds.
+pub async fn send(provider: &dyn SocialProvider, client: &Client, request: &ProxyRequest) -> Result<UpstreamResponse, ProviderError> {
+    let resp = send_raw(provider, client, request).await?;
+    Ok(read_upstream(provider, resp).await)
+}
+
+/// `send` without reading the body, so the caller can stream it. Requests with `raw` set get
+/// `SOCIAL_RAW_TIMEOUT_SECONDS` for the whole transfer instead of the usual request timeout.
+pub async fn send_raw(provider: &dyn SocialProvider, client: &Client, request: &ProxyRequest) -> Result<reqwest::Response, ProviderError> {
+    let source = provider.source();
+    let host = url::Url::parse(&provider.base_url())
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_lowercase()))
+        .unwrap_or_default();
+    let breakers = circuit_breakers();
+    breakers.allow(&host, &source).map_err(|retry_in| ProviderError::CircuitOpen {
+        provider: source.clone(),
+        host: host.clone(),
+        retry_in,
+    })?;
+
+    let result = send_with_retries(provider, client, request).await;
+    match &result {
+        Ok(resp) if resp.status().is_server_error() => breakers.record_failure(&host),
+        Ok(_) => breakers.record_success(&host),
+        Err(ProviderError::Request(_) | ProviderError::Timeout) => breakers.record_failure(&host),
+        Err(_) => {}
+    }
+    result
+}
+
+// Helper function for one call: waits for the provider's client-side rate limit and takes a key
+// from its credential pool. Timeouts, connection failures, 429s and 5xxs are retried with
+// jittered exponential backoff, or after the upstream's `Retry-After` / `x-ratelimit-*-reset`
+// when it gives one; a rejected or rate limited key is swapped for another straight away. The
+// last response is returned once retries run out.
+async fn send_with_retries(
+    provider: &dyn SocialProvider,
+    client: &Client,
+    request: &ProxyRequest,
+) -> Result<reqwest::Response, ProviderError> {
+    let limiter = upstream_limiter();
+    let policy = limiter.policy();
+    let credentials = credential_pools();
+    let pool = provider.credential_pool();
+    let source = provider.source();
+    let params = request.params_for(provider.post_layout());
+    let url = upstream_url(provider, &request.path).ok_or_else(|| ProviderError::InvalidPath(request.path.clone()))?;
+    let (accept, request_timeout) = if request.raw {
+        ("*/*", policy.raw_timeout)
+    } else {
+        ("application/json", policy.request_timeout)
+    };
+
+    let mut attempt = 0;
+    let resp = loop {
+        limiter.wait_for_slot(&source).await.map_err(ProviderError::RateLimited)?;
+        let credential = match credentials.checkout(pool) {
+            Ok(credential) => credential,
+            Err(CheckoutError::Missing(name)) => return Err(ProviderError::MissingCredential(name.to_string())),
+            Err(CheckoutError::CoolingDown(wait)) if wait <= policy.max_wait => {
+                sleep(wait).await;
+                continue;
+            }
+            Err(CheckoutError::CoolingDown(wait)) => {
+                return Err(ProviderError::CredentialsCoolingDown {
+                    pool: pool.to_string(),
+                    retry_in: wait,
+                })
+            }
+        };
+
+        let builder = if request.is_get() {
+            client.get(&url).query(&provider.query(&params))
+        } else {
+            client.post(&url).json(&params.clone().unwrap_or(Value::Null))
+        };
+        let result = provider
+            .authorize(builder.header("accept", accept).timeout(request_timeout), &credential.secret)
+            .send()
+            .await;
+
+        let resp = match result {
+            Ok(resp) => resp,
+            // A failed connection never reached the upstream; a timed out request may have been acted on
+            Err(e) if (e.is_connect() || (e.is_timeout() && request.is_retry_safe())) && attempt < policy.max_retries => {
+                sleep(policy.backoff(attempt)).await;
+                attempt += 1;
+                continue;
+            }
+            Err(e) if e.is_timeout() => return Err(ProviderError::Timeout),
+            Err(e) => return Err(e.into()),
+        };
+
+        let status = resp.status().as_u16();
+        credentials.record(&credential, status, upstream::requested_wait(resp.headers()));
+        if let Some(reset) = upstream::exhausted_quota_reset(resp.headers()) {
+            // The quota belongs to the key; hold back the provider only once no other key is left
+            credentials.cool_down(&credential, reset);
+            if !credentials.has_available(pool) {
+                limiter.pause(&source, reset);
+            }
+        }
+        if matches!(status, 401 | 403 | 429) && attempt < policy.max_retries && credentials.has_available(pool) {
+            attempt += 1;
+            continue;
+        }
+        let retryable = status == 429 || (upstream::is_retryable(status) && request.is_retry_safe());
+        if retryable && attempt < policy.max_retries {
+            let delay = upstream::requested_wait(resp.headers()).unwrap_or_else(|| policy.backoff(attempt));
+            if delay <= policy.max_wait {
+                sleep(delay).await;
+                attempt += 1;
+                continue;
+            }
+        }
+        break resp;
+    };
+    Ok(resp)
+}
+
+// Helper function to read an upstream response body and map it
+async fn read_upstream(provider: &dyn SocialProvider, resp: reqwest::Response) -> UpstreamResponse {
+    let status = resp.status().as_u16();
+    let retry_after = (status == 429).then(|| upstream::requested_wait(resp.headers())).flatten();
+    let is_json = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+        .unwrap_or("")
+        .contains("application/json");
+
+    let body = if is_json {
+        UpstreamBody::Json(resp.json::<Value>().await.ok())
+    } else {
+        UpstreamBody::Text(resp.text().await.ok())
+    };
+    let mut upstream = upstream_from(provider, status, body);
+    upstream.retry_after = retry_after;
+    upstream
+}
+
+// Helper function to map posts and the next cursor out of an upstream body
+pub(crate) fn upstream_from(provider: &dyn SocialProvider, status: u16, body: UpstreamBody) -> UpstreamResponse {
+    let (posts, next_cursor) = match (&body, provider.post_layout()) {
+        (UpstreamBody::Json(Some(data)), Some(layout)) => (Some(layout.parse(data)), layout.next_cursor(data)),
+        _ => (None, None),
+    };
+    UpstreamResponse {
+        status,
+        body,
+        posts,
+        next_cursor,
+        cache_key: None,
+        cached: false,
+        retry_after: None,
+    }
+}
+
+/// `send` behind the response cache. With `bypass` (`Cache-Control: no-cache`) the cache is not
+/// read, but the fresh response still replaces what was cached.
+pub async fn send_cached(
+    cache: &ResponseCache,
+    provider: &dyn SocialProvider,
+    client: &Client,
+    request: &ProxyRequest,
+    bypass: bool,
+) -> Result<UpstreamResponse, ProviderError> {
+    let Some(ttl) = cache.ttl(&provider.source()) else {
+        return send(provider, client, request).await;
+    };
+    let key = ResponseCache::key(provider, request);
+    if !bypass {
+        if let Some(hit) = cache.get(provider, &key, ttl).await {
+            return Ok(hit);
+        }
+    }
+
+    let mut upstream = send(provider, client, request).await?;
+    if (200..300).contains(&upstream.status) {
+        upstream.cache_key = Some(key.clone());
+        cache.put(&key, &upstream, ttl);
+    }
+    Ok(upstream)
+}
+
+/// Follow the provider's cursors until the request's `max_items`/`max_pages` (capped by `limits`),
+/// the last page, or the time budget. Pages are never split, so the result may slightly exceed
+/// `max_items` and resuming from `next_cursor` skips nothing. Only the first page's error fails
+/// the call; later failures end the walk at the cursor that failed.
+pub async fn send_pages(
+    cache: &ResponseCache,
+    provider: &dyn SocialProvider,
+    client: &Client,
+    request: &ProxyRequest,
+    limits: &SocialPagingLimits,
+    bypass_cache: bool,
+) -> Result<PagedResponse, ProviderError> {
+    let max_pages = request.max_pages.unwrap_or(limits.max_pages).clamp(1, limits.max_pages);
+    let max_items = request.max_items.unwrap_or(limits.max_items).clamp(1, limits.max_items);
+    let deadline = Instant::now() + Duration::from_secs(limits.time_budget_seconds);
+
+    let mut paged = PagedResponse {
+        status: 0,
+        pages: Vec::new(),
+        posts: Vec::new(),
+        next_cursor: request.cursor.clone(),
+    };
+    let mut seen = HashSet::new();
+    let mut page_request = request.clone();
+
+    loop {
+        page_request.cursor = paged.next_cursor.clone();
+        let fetch = send_cached(cache, provider, client, &page_request, bypass_cache);
+        let mut upstream = if paged.pages.is_empty() {
+            fetch.await?
+        } else {
+            match timeout_at(deadline, fetch).await {
+                Ok(Ok(upstream)) => upstream,
+                _ => break,
+            }
+        };
+
+        let posts = upstream.posts.take().unwrap_or_default();
+        let next_cursor = upstream.next_cursor.clone();
+        let succeeded = (200..300).contains(&upstream.status);
+        paged.status = upstream.status;
+        paged.pages.push(upstream);
+        if !succeeded {
+            break;
+        }
+
+        let mut added = 0;
+        for post in posts {
+            if seen.insert((post.platform.clone(), post.post_id.clone())) {
+                paged.posts.push(post);
+                added += 1;
+            }
+        }
+        paged.next_cursor = next_cursor;
+
+        // A page with nothing new means the cursor is not moving us forward
+        if paged.next_cursor.is_none()
+            || added == 0
+            || paged.posts.len() >= max_items
+            || paged.pages.len() >= max_pages
+            || Instant::now() >= deadline
+        {
+            break;
+        }
+    }
+
+    Ok(paged)
+}
+
+/// Named providers behind the dedicated `/social/...` routes and `/social/providers/{name}`.
+/// Built-ins can be extended with `SOCIAL_PROVIDERS`, a JSON list of
+/// `{"name", "kind": "tikhub" | "rapidapi", "target", "posts"}` where `target` is the TikHub
+/// service or RapidAPI host and the optional `posts` is a `PostLayout` such as `twitter_graphql`.
+pub struct ProviderRegistry {
+    providers: HashMap<String, Arc<dyn SocialProvider>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProviderConfig {
+    name: String,
+    kind: String,
+    target: String,
+    #[serde(default)]
+    posts: Option<PostLayout>,
+}
+
+impl ProviderRegistry {
+    pub fn from_env() -> Self {
+        let mut registry = Self {
+            providers: HashMap::new(),
+        };
+        registry.register(
+            "tikhub_twitter",
+            TikHubProvider::new("tikhub_twitter", "twitter/web")
+                .with_query(TikHubQuery::TwitterSearch)
+                .with_posts(PostLayout::TikhubTwitter),
+        );
+        registry.register(
+            "tikhub_tiktok",
+            TikHubProvider::new("tikhub_tiktok", "tiktok/web")
+                .with_query(TikHubQuery::TikTokSearch)
+                .with_posts(PostLayout::TikhubTiktok),
+        );
+        registry.register(
+            "rapidapi_instagram",
+            RapidApiProvider::new("rapidapi_instagram", "instagram-scraper-api2.p.rapidapi.com").with_posts(PostLayout::Instagram),
+        );
+        registry.register(
+            "rapidapi_twitter_v24",
+            RapidApiProvider::new("rapidapi_twitter_v24", "twitter-v24.p.rapidapi.com").with_posts(PostLayout::TwitterGraphql),
+        );
+
+        if let Some(configs) = crate::config::env_json::<Vec<ProviderConfig>>("SOCIAL_PROVIDERS") {
+            for config in configs {
+                match config.kind.as_str() {
+                    "tikhub" => {
+                        let mut provider = TikHubProvider::new(&config.name, &config.target);
+                        if let Some(layout) = config.posts {
+                            provider = provider.with_posts(layout);
+                        }
+                        registry.register(&config.name, provider)
+                    }
+                    "rapidapi" => {
+                        let mut provider = RapidApiProvider::new(&config.name, &config.target);
+                        if let Some(layout) = config.posts {
+                            provider = provider.with_posts(layout);
+                        }
+                        registry.register(&config.name, provider)
+                    }
+                    other => eprintln!(
+                        "[Config] Ignoring SOCIAL_PROVIDERS entry {}: unknown kind `{}`",
+                        config.name, other
+                    ),
+                }
+            }
+        }
+
+        registry
+    }
+
+    pub fn register(&mut self, name: &str, provider: impl SocialProvider + 'static) {
+        self.providers.insert(name.to_string(), Arc::new(provider));
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn SocialProvider>> {
+        self.providers.get(name).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn upstream_url_appends_the_path_to_the_base_url() {
+        let provider = TikHubProvider::new("tikhub_twitter", "twitter/web");
+
+        assert_eq!(
+            upstream_url(&provider, "/fetch_search_timeline").as_deref(),
+            Some("https://api.tikhub.io/api/v1/twitter/web/fetch_search_timeline")
+        );
+        assert_eq!(
+            upstream_url(&RapidApiProvider::new("rapidapi_twitter_v24", "twitter-v24.p.rapidapi.com"), "search/").as_deref(),
+            Some("https://twitter-v24.p.rapidapi.com/search/")
+        );
+    }
+
+    #[test]
+    fn upstream_url_rejects_paths_that_leave_the_base_url() {
+        let provider = TikHubProvider::new("tikhub_twitter", "twitter/web");
+
+        for path in [
+            "../../tiktok/web/fetch_user_profile",
+            "fetch/../../../tiktok/web/fetch_user_profile",
+            "%2e%2e/%2E%2E/tiktok/web/fetch_user_profile",
+            "..\\..\\tiktok/web/fetch_user_profile",
+            "./fetch_search_timeline",
+            "https://attacker.example.com/",
+        ] {
+            assert_eq!(upstream_url(&provider, path), None, "{}", path);
+        }
+    }
+
+    #[test]
+    fn only_gets_and_marked_posts_are_retry_safe() {
+        let request = |body: Value| serde_json::from_value::<ProxyRequest>(body).unwrap();
+
+        assert!(request(json!({"path": "search"})).is_retry_safe());
+        assert!(request(json!({"path": "search", "method": "get"})).is_retry_safe());
+        assert!(!request(json!({"path": "search", "method": "POST"})).is_retry_safe());
+        assert!(request(json!({"path": "search", "method": "POST", "retry_safe": true})).is_retry_safe());
+    }
+}
@@ -0,0 +1,439 @@
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Engagement counts; `None` when the upstream does not report them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PostMetrics {
+    pub likes: Option<i64>,
+    pub comments: Option<i64>,
+    pub shares: Option<i64>,
+    pub views: Option<i64>,
+}
+
+/// A post in the same shape whichever provider it came from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SocialPost {
+    /// `twitter`, `tiktok` or `instagram`
+    pub platform: String,
+    pub post_id: String,
+    /// Handle of the author, without `@`
+    pub author: Option<String>,
+    pub text: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub metrics: PostMetrics,
+    pub media_urls: Vec<String>,
+    pub permalink: Option<String>,
+}
+
+/// Upstream response layouts we know how to map to `SocialPost`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostLayout {
+    /// TikHub `twitter/web` search timeline
+    TikhubTwitter,
+    /// TikHub `tiktok/web` search results
+    TikhubTiktok,
+    /// RapidAPI `instagram-scraper-api2` items
+    Instagram,
+    /// Raw Twitter GraphQL (`tweet_results`), as returned by RapidAPI `twitter-v24`
+    TwitterGraphql,
+}
+
+impl PostLayout {
+    pub fn parse(&self, data: &Value) -> Vec<SocialPost> {
+        match self {
+            PostLayout::TikhubTwitter => tikhub_twitter_posts(data),
+            PostLayout::TikhubTiktok => tikhub_tiktok_posts(data),
+            PostLayout::Instagram => instagram_posts(data),
+            PostLayout::TwitterGraphql => twitter_graphql_posts(data),
+        }
+    }
+
+    /// Request param that carries the cursor for the next page
+    pub fn cursor_param(&self) -> &'static str {
+        match self {
+            PostLayout::TikhubTwitter | PostLayout::TwitterGraphql => "cursor",
+            PostLayout::TikhubTiktok => "offset",
+            PostLayout::Instagram => "pagination_token",
+        }
+    }
+
+    /// Cursor for the page after `data`, `None` on the last page
+    pub fn next_cursor(&self, data: &Value) -> Option<String> {
+        match self {
+            PostLayout::TikhubTwitter => string_at(data, &[&["data", "next_cursor"], &["next_cursor"]]),
+            PostLayout::TikhubTiktok => {
+                let has_more = match at(data, &["data", "has_more"]).or_else(|| data.get("has_more")) {
+                    Some(Value::Bool(more)) => *more,
+                    Some(Value::Number(more)) => more.as_i64() != Some(0),
+                    _ => true,
+                };
+                has_more
+                    .then(|| string_at(data, &[&["data", "cursor"], &["cursor"]]))
+                    .flatten()
+            }
+            PostLayout::Instagram => string_at(data, &[&["pagination_token"], &["data", "pagination_token"]]),
+            PostLayout::TwitterGraphql => bottom_cursor(data),
+        }
+    }
+}
+
+// Helper function to follow a path of object keys
+fn at<'a>(value: &'a Value, path: &[&str]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |value, key| value.get(*key))
+}
+
+// Helper function to read a string at the first path that has one
+fn string_at(value: &Value, paths: &[&[&str]]) -> Option<String> {
+    paths.iter().find_map(|path| match at(value, path)? {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    })
+}
+
+// Helper function to read a count that may be a number or a numeric string
+fn count_at(value: &Value, paths: &[&[&str]]) -> Option<i64> {
+    paths.iter().find_map(|path| match at(value, path)? {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    })
+}
+
+// Helper function for unix timestamps in seconds
+fn unix_time_at(value: &Value, paths: &[&[&str]]) -> Option<DateTime<Utc>> {
+    count_at(value, paths).and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+}
+
+// Twitter's `Wed Oct 10 20:19:24 +0000 2018` format
+fn twitter_time(value: Option<String>) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_str(&value?, "%a %b %d %H:%M:%S %z %Y")
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+// Helper function to find the first array at any of the paths
+fn array_at<'a>(value: &'a Value, paths: &[&[&str]]) -> &'a [Value] {
+    paths
+        .iter()
+        .find_map(|path| at(value, path)?.as_array())
+        .map(|items| items.as_slice())
+        .unwrap_or(&[])
+}
+
+fn tweet_permalink(author: Option<&String>, id: &str) -> String {
+    match author {
+        Some(author) => format!("https://x.com/{}/status/{}", author, id),
+        None => format!("https://x.com/i/status/{}", id),
+    }
+}
+
+fn tikhub_twitter_posts(data: &Value) -> Vec<SocialPost> {
+    array_at(data, &[&["data", "timeline"], &["timeline"]])
+        .iter()
+        .filter(|entry| entry.get("type").and_then(Value::as_str).is_none_or(|kind| kind == "tweet"))
+        .filter_map(|entry| {
+            let post_id = string_at(entry, &[&["tweet_id"], &["id"]])?;
+            let author = string_at(entry, &[&["screen_name"], &["user_info", "screen_name"]]);
+            let media_urls = ["photo", "video"]
+                .iter()
+                .flat_map(|kind| array_at(entry, &[&["media", kind]]))
+                .filter_map(|media| string_at(media, &[&["media_url_https"], &["url"]]))
+                .collect();
+            Some(SocialPost {
+                platform: "twitter".to_string(),
+                permalink: Some(tweet_permalink(author.as_ref(), &post_id)),
+                text: string_at(entry, &[&["text"]]),
+                created_at: twitter_time(string_at(entry, &[&["created_at"]])),
+                metrics: PostMetrics {
+                    likes: count_at(entry, &[&["favorites"]]),
+                    comments: count_at(entry, &[&["replies"]]),
+                    shares: count_at(entry, &[&["retweets"]]),
+                    views: count_at(entry, &[&["views"]]),
+                },
+                media_urls,
+                author,
+                post_id,
+            })
+        })
+        .collect()
+}
+
+fn tikhub_tiktok_posts(data: &Value) -> Vec<SocialPost> {
+    array_at(data, &[&["data", "data"], &["data", "item_list"], &["data", "itemList"], &["item_list"]])
+        .iter()
+        // Search results wrap videos in `item`; listing endpoints return them directly
+        .map(|entry| entry.get("item").unwrap_or(entry))
+        .filter_map(|item| {
+            let post_id = string_at(item, &[&["id"], &["aweme_id"]])?;
+            let author = string_at(item, &[&["author", "uniqueId"], &["author", "unique_id"]]);
+            let permalink = author
+                .as_ref()
+                .map(|author| format!("https://www.tiktok.com/@{}/video/{}", author, post_id));
+            Some(SocialPost {
+                platform: "tiktok".to_string(),
+                text: string_at(item, &[&["desc"]]),
+                created_at: unix_time_at(item, &[&["createTime"], &["create_time"]]),
+                metrics: PostMetrics {
+                    likes: count_at(item, &[&["stats", "diggCount"], &["statistics", "digg_count"]]),
+                    comments: count_at(item, &[&["stats", "commentCount"], &["statistics", "comment_count"]]),
+                    shares: count_at(item, &[&["stats", "shareCount"], &["statistics", "share_count"]]),
+                    views: count_at(item, &[&["stats", "playCount"], &["statistics", "play_count"]]),
+                },
+                media_urls: string_at(item, &[&["video", "cover"], &["video", "playAddr"]]).into_iter().collect(),
+                permalink,
+                author,
+                post_id,
+            })
+        })
+        .collect()
+}
+
+fn instagram_posts(data: &Value) -> Vec<SocialPost> {
+    array_at(data, &[&["data", "items"], &["items"]])
+        .iter()
+        .filter_map(|item| {
+            let post_id = string_at(item, &[&["id"], &["pk"]])?;
+            let permalink = string_at(item, &[&["code"]]).map(|code| format!("https://www.instagram.com/p/{}/", code));
+            let media_urls = string_at(item, &[&["video_url"], &["thumbnail_url"], &["display_url"]])
+                .into_iter()
+                .collect();
+            Some(SocialPost {
+                platform: "instagram".to_string(),
+                author: string_at(item, &[&["user", "username"], &["owner", "username"]]),
+                text: string_at(item, &[&["caption", "text"], &["caption_text"]]),
+                created_at: unix_time_at(item, &[&["taken_at"], &["caption", "created_at"]]),
+                metrics: PostMetrics {
+                    likes: count_at(item, &[&["like_count"]]),
+                    comments: count_at(item, &[&["comment_count"]]),
+                    shares: count_at(item, &[&["reshare_count"], &["share_count"]]),
+                    views: count_at(item, &[&["play_count"], &["view_count"]]),
+                },
+                media_urls,
+                permalink,
+                post_id,
+            })
+        })
+        .collect()
+}
+
+// Helper function to collect every `tweet_results.result` in a GraphQL response
+fn collect_tweet_results<'a>(value: &'a Value, found: &mut Vec<&'a Value>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(result) = map.get("tweet_results").and_then(|r| r.get("result")) {
+                // Tweets with visibility restrictions wrap the tweet once more
+                found.push(result.get("tweet").unwrap_or(result));
+            }
+            for child in map.values() {
+                collect_tweet_results(child, found);
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_tweet_results(item, found)),
+        _ => {}
+    }
+}
+
+fn twitter_graphql_posts(data: &Value) -> Vec<SocialPost> {
+    let mut results = Vec::new();
+    collect_tweet_results(data, &mut results);
+
+    let mut seen = std::collections::HashSet::new();
+    results
+        .into_iter()
+        .filter_map(|tweet| {
+            let legacy = tweet.get("legacy")?;
+            let post_id = string_at(tweet, &[&["rest_id"]]).or_else(|| string_at(legacy, &[&["id_str"]]))?;
+            if !seen.insert(post_id.clone()) {
+                return None;
+            }
+            let author = string_at(
+                tweet,
+                &[
+                    &["core", "user_results", "result", "legacy", "screen_name"],
+                    &["core", "user_results", "result", "core", "screen_name"],
+                ],
+            );
+            let media_urls = array_at(legacy, &[&["extended_entities", "media"], &["entities", "media"]])
+                .iter()
+                .filter_map(|media| string_at(media, &[&["media_url_https"]]))
+                .collect();
+            Some(SocialPost {
+                platform: "twitter".to_string(),
+                permalink: Some(tweet_permalink(author.as_ref(), &post_id)),
+                text: string_at(tweet, &[&["note_tweet", "note_tweet_results", "result", "text"]])
+                    .or_else(|| string_at(legacy, &[&["full_text"]])),
+                created_at: twitter_time(string_at(legacy, &[&["created_at"]])),
+                metrics: PostMetrics {
+                    likes: count_at(legacy, &[&["favorite_count"]]),
+                    comments: count_at(legacy, &[&["reply_count"]]),
+                    shares: count_at(legacy, &[&["retweet_count"]]),
+                    views: count_at(tweet, &[&["views", "count"]]),
+                },
+                media_urls,
+                author,
+                post_id,
+            })
+        })
+        .collect()
+}
+
+// Helper function to find the `Bottom` timeline cursor anywhere in a GraphQL response
+fn bottom_cursor(value: &Value) -> Option<String> {
+    match value {
+        Value::Object(map) => {
+            if map.get("cursorType").and_then(Value::as_str) == Some("Bottom") {
+                if let Some(cursor) = map.get("value").and_then(Value::as_str) {
+                    return Some(cursor.to_string());
+                }
+            }
+            map.values().find_map(bottom_cursor)
+        }
+        Value::Array(items) => items.iter().find_map(bottom_cursor),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(raw: &str) -> Value {
+        serde_json::from_str(raw).unwrap()
+    }
+
+    fn time(secs: i64) -> Option<DateTime<Utc>> {
+        Utc.timestamp_opt(secs, 0).single()
+    }
+
+    #[test]
+    fn tikhub_twitter_timeline() {
+        let data = fixture(include_str!("fixtures/tikhub_twitter.json"));
+        let posts = PostLayout::TikhubTwitter.parse(&data);
+
+        // The user entry and the tweet without an id are skipped
+        assert_eq!(posts.len(), 2);
+        let first = &posts[0];
+        assert_eq!(first.platform, "twitter");
+        assert_eq!(first.post_id, "1790000000000000001");
+        assert_eq!(first.author.as_deref(), Some("rustlang"));
+        assert_eq!(first.permalink.as_deref(), Some("https://x.com/rustlang/status/1790000000000000001"));
+        assert_eq!(first.created_at, time(1721833200));
+        assert_eq!(first.metrics.likes, Some(1520));
+        assert_eq!(first.metrics.comments, Some(48));
+        assert_eq!(first.metrics.shares, Some(310));
+        assert_eq!(first.metrics.views, Some(98000));
+        assert_eq!(first.media_urls, vec!["https://pbs.twimg.com/media/a.jpg", "https://video.twimg.com/b.mp4"]);
+
+        // Numeric id, nested author, unparseable date and no metrics
+        let second = &posts[1];
+        assert_eq!(second.post_id, "1790000000000000002");
+        assert_eq!(second.author.as_deref(), Some("ferris"));
+        assert_eq!(second.created_at, None);
+        assert_eq!(second.metrics.likes, None);
+        assert!(second.media_urls.is_empty());
+
+        assert_eq!(PostLayout::TikhubTwitter.next_cursor(&data).as_deref(), Some("DAABCgABGQ"));
+    }
+
+    #[test]
+    fn tikhub_tiktok_search_and_listing_shapes() {
+        let data = fixture(include_str!("fixtures/tikhub_tiktok.json"));
+        let posts = PostLayout::TikhubTiktok.parse(&data);
+
+        assert_eq!(posts.len(), 2);
+        let search = &posts[0];
+        assert_eq!(search.platform, "tiktok");
+        assert_eq!(search.post_id, "7390000000000000001");
+        assert_eq!(search.author.as_deref(), Some("ferris.crab"));
+        assert_eq!(search.permalink.as_deref(), Some("https://www.tiktok.com/@ferris.crab/video/7390000000000000001"));
+        assert_eq!(search.created_at, time(1721833200));
+        assert_eq!(search.metrics.views, Some(250000));
+        assert_eq!(search.media_urls, vec!["https://p16.tiktokcdn.com/cover.jpeg"]);
+
+        // Unwrapped item with `aweme_id`, snake_case stats and string numbers
+        let listing = &posts[1];
+        assert_eq!(listing.post_id, "7390000000000000002");
+        assert_eq!(listing.author.as_deref(), Some("borrowck"));
+        assert_eq!(listing.created_at, time(1721836800));
+        assert_eq!(listing.metrics.likes, Some(7));
+        assert_eq!(listing.metrics.shares, Some(0));
+        assert_eq!(listing.media_urls, vec!["https://v16.tiktokcdn.com/play.mp4"]);
+
+        assert_eq!(PostLayout::TikhubTiktok.next_cursor(&data).as_deref(), Some("20"));
+    }
+
+    #[test]
+    fn tikhub_tiktok_stops_when_has_more_is_off() {
+        let data = serde_json::json!({ "data": { "data": [], "has_more": false, "cursor": 40 } });
+        assert_eq!(PostLayout::TikhubTiktok.next_cursor(&data), None);
+    }
+
+    #[test]
+    fn instagram_items_with_renamed_fields() {
+        let data = fixture(include_str!("fixtures/instagram.json"));
+        let posts = PostLayout::Instagram.parse(&data);
+
+        assert_eq!(posts.len(), 2);
+        let first = &posts[0];
+        assert_eq!(first.platform, "instagram");
+        assert_eq!(first.post_id, "3400000000000000001_12345");
+        assert_eq!(first.author.as_deref(), Some("rustacean"));
+        assert_eq!(first.text.as_deref(), Some("Sunset over the harbour"));
+        assert_eq!(first.permalink.as_deref(), Some("https://www.instagram.com/p/C9abcDEF/"));
+        // `taken_at` wins over the caption's timestamp, the video over its thumbnail
+        assert_eq!(first.created_at, time(1721833200));
+        assert_eq!(first.media_urls, vec!["https://scontent.cdninstagram.com/v.mp4"]);
+        assert_eq!(first.metrics.shares, Some(3));
+        assert_eq!(first.metrics.views, Some(4500));
+
+        let second = &posts[1];
+        assert_eq!(second.post_id, "3400000000000000002");
+        assert_eq!(second.author.as_deref(), Some("crab.photos"));
+        assert_eq!(second.text.as_deref(), Some("Renamed caption and owner fields"));
+        assert_eq!(second.permalink, None);
+        assert_eq!(second.created_at, None);
+        assert_eq!(second.metrics.likes, Some(15));
+        assert_eq!(second.media_urls, vec!["https://scontent.cdninstagram.com/d.jpg"]);
+
+        assert_eq!(PostLayout::Instagram.next_cursor(&data).as_deref(), Some("QVFEMk9"));
+    }
+
+    #[test]
+    fn twitter_graphql_timeline() {
+        let data = fixture(include_str!("fixtures/twitter_graphql.json"));
+        let posts = PostLayout::TwitterGraphql.parse(&data);
+
+        // The duplicate and the tombstone without `legacy` are dropped
+        assert_eq!(posts.len(), 2);
+        let first = &posts[0];
+        assert_eq!(first.post_id, "1800000000000000001");
+        assert_eq!(first.author.as_deref(), Some("rustlang"));
+        assert_eq!(first.text.as_deref(), Some("A long note tweet that replaces the truncated text"));
+        assert_eq!(first.created_at, time(1722504600));
+        assert_eq!(first.metrics.likes, Some(900));
+        assert_eq!(first.metrics.views, Some(12000));
+        assert_eq!(first.media_urls, vec!["https://pbs.twimg.com/media/c.jpg"]);
+
+        // Visibility-restricted tweet: id from `legacy.id_str`, author under the newer `core` path
+        let restricted = &posts[1];
+        assert_eq!(restricted.post_id, "1800000000000000002");
+        assert_eq!(restricted.author.as_deref(), Some("ferris"));
+        assert_eq!(restricted.text.as_deref(), Some("Restricted tweet, author under the new core path"));
+        assert_eq!(restricted.metrics.views, None);
+        assert_eq!(restricted.media_urls, vec!["https://pbs.twimg.com/media/d.jpg"]);
+
+        assert_eq!(PostLayout::TwitterGraphql.next_cursor(&data).as_deref(), Some("BOTTOM_CURSOR"));
+    }
+
+    #[test]
+    fn unexpected_shapes_yield_no_posts() {
+        for layout in [PostLayout::TikhubTwitter, PostLayout::TikhubTiktok, PostLayout::Instagram, PostLayout::TwitterGraphql] {
+            assert!(layout.parse(&serde_json::json!({ "error": "quota exceeded" })).is_empty());
+            assert!(layout.parse(&serde_json::json!({ "data": { "items": "not a list", "timeline": 3 } })).is_empty());
+            assert!(layout.parse(&Value::Null).is_empty());
+        }
+    }
+}
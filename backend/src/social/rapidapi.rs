@@ -0,0 +1,53 @@
+use reqwest::RequestBuilder;
+
+use super::{PostLayout, SocialProvider};
+
+/// An API hosted on RapidAPI, authenticated with a key from the `rapidapi` credential pool
+pub struct RapidApiProvider {
+    source: String,
+    host: String,
+    posts: Option<PostLayout>,
+}
+
+impl RapidApiProvider {
+    pub fn new(source: &str, host: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            host: host.to_string(),
+            posts: None,
+        }
+    }
+
+    pub fn with_posts(mut self, layout: PostLayout) -> Self {
+        self.posts = Some(layout);
+        self
+    }
+}
+
+impl SocialProvider for RapidApiProvider {
+    fn source(&self) -> String {
+        self.source.clone()
+    }
+
+    fn base_url(&self) -> String {
+        format!("https://{}/", self.host)
+    }
+
+    fn target(&self) -> String {
+        self.host.clone()
+    }
+
+    fn credential_pool(&self) -> &'static str {
+        "rapidapi"
+    }
+
+    fn authorize(&self, request: RequestBuilder, credential: &str) -> RequestBuilder {
+        request
+            .header("x-rapidapi-key", credential)
+            .header("x-rapidapi-host", self.host.as_str())
+    }
+
+    fn post_layout(&self) -> Option<PostLayout> {
+        self.posts
+    }
+}
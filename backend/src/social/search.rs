@@ -0,0 +1,46 @@
+use chrono::{DateTime, Utc};
+use serde_json::json;
+
+use super::ProxyRequest;
+
+/// Platforms `/social/search` can fan out to
+pub const SEARCH_PLATFORMS: [&str; 3] = ["twitter", "tiktok", "instagram"];
+
+/// Registered provider and request for a keyword search on one platform. Twitter gets the
+/// `since:`/`until:` operators so the upstream filters too; other platforms are filtered
+/// after normalization.
+pub fn search_request(
+    platform: &str,
+    keyword: &str,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> Option<(&'static str, ProxyRequest)> {
+    let (provider, path, params) = match platform {
+        "twitter" => {
+            let mut query = keyword.to_string();
+            if let Some(since) = since {
+                query.push_str(&format!(" since:{}", since.format("%Y-%m-%d")));
+            }
+            if let Some(until) = until {
+                query.push_str(&format!(" until:{}", until.format("%Y-%m-%d")));
+            }
+            ("tikhub_twitter", "fetch_search_timeline", json!({"keyword": query, "search_type": "Latest"}))
+        }
+        "tiktok" => ("tikhub_tiktok", "fetch_search_video", json!({"keyword": keyword})),
+        // Instagram search is by hashtag: `#rust lang` becomes `rustlang`
+        "instagram" => {
+            let hashtag: String = keyword.chars().filter(|c| c.is_alphanumeric() || *c == '_').collect();
+            ("rapidapi_instagram", "v1/hashtag", json!({"hashtag": hashtag}))
+        }
+        _ => return None,
+    };
+
+    Some((
+        provider,
+        ProxyRequest {
+            path: path.to_string(),
+            params: Some(params),
+            ..Default::default()
+        },
+    ))
+}
@@ -0,0 +1,119 @@
+use reqwest::RequestBuilder;
+use serde_json::Value;
+
+use super::{param_value, params_to_query, PostLayout, SocialProvider};
+
+const TIKHUB_BASE: &str = "https://api.tikhub.io/api/v1";
+
+/// How a TikHub service expects its query params
+#[derive(Debug, Clone, Copy, Default)]
+pub enum TikHubQuery {
+    /// Params are passed through unchanged
+    #[default]
+    Passthrough,
+    /// Twitter web search: `q` becomes `keyword`, `search_type` defaults to `Top`
+    TwitterSearch,
+    /// TikTok web search: `q` becomes `keyword`, `count=20` and `offset=0` by default
+    TikTokSearch,
+}
+
+/// A TikHub service (e.g. `twitter/web`), authenticated with a key from the `tikhub` credential
+/// pool as a Bearer token
+pub struct TikHubProvider {
+    source: String,
+    service: String,
+    query: TikHubQuery,
+    posts: Option<PostLayout>,
+}
+
+impl TikHubProvider {
+    pub fn new(source: &str, service: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            service: service.trim_matches('/').to_string(),
+            query: TikHubQuery::default(),
+            posts: None,
+        }
+    }
+
+    pub fn with_query(mut self, query: TikHubQuery) -> Self {
+        self.query = query;
+        self
+    }
+
+    pub fn with_posts(mut self, layout: PostLayout) -> Self {
+        self.posts = Some(layout);
+        self
+    }
+}
+
+impl SocialProvider for TikHubProvider {
+    fn source(&self) -> String {
+        self.source.clone()
+    }
+
+    fn base_url(&self) -> String {
+        format!("{}/{}/", TIKHUB_BASE, self.service)
+    }
+
+    fn target(&self) -> String {
+        self.service.clone()
+    }
+
+    fn credential_pool(&self) -> &'static str {
+        "tikhub"
+    }
+
+    fn authorize(&self, request: RequestBuilder, credential: &str) -> RequestBuilder {
+        request.header("Authorization", format!("Bearer {}", credential))
+    }
+
+    fn query(&self, params: &Option<Value>) -> Vec<(String, String)> {
+        match self.query {
+            TikHubQuery::Passthrough => params_to_query(params),
+            TikHubQuery::TwitterSearch => search_query(params, &[("search_type", "Top")]),
+            TikHubQuery::TikTokSearch => search_query(params, &[("count", "20"), ("offset", "0")]),
+        }
+    }
+
+    fn post_layout(&self) -> Option<PostLayout> {
+        self.posts
+    }
+}
+
+// Helper function for TikHub search endpoints: use `keyword` (falling back to `q`), carry the
+// given params with their defaults, then pass the remaining params through
+fn search_query(params: &Option<Value>, defaults: &[(&str, &str)]) -> Vec<(String, String)> {
+    let mut query: Vec<(String, String)> = Vec::new();
+    let map = match params {
+        Some(Value::Object(map)) => Some(map),
+        _ => None,
+    };
+
+    if let Some(map) = map {
+        // Prefer `keyword`, fallback to `q`
+        if let Some(v) = map.get("keyword").or_else(|| map.get("q")) {
+            query.push(("keyword".to_string(), param_value(v)));
+        }
+    }
+
+    for (name, default) in defaults {
+        let value = map
+            .and_then(|map| map.get(*name))
+            .map(param_value)
+            .unwrap_or_else(|| default.to_string());
+        query.push((name.to_string(), value));
+    }
+
+    if let Some(map) = map {
+        for (k, v) in map.iter() {
+            if k == "q" || k == "keyword" || defaults.iter().any(|(name, _)| name == k) {
+                continue;
+            }
+            query.push((k.clone(), param_value(v)));
+        }
+    }
+
+    // If no keyword provided, we still return any extra params; TikHub may error accordingly.
+    query
+}
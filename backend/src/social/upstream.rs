@@ -0,0 +1,188 @@
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use tokio::time::{sleep, Duration, Instant};
+
+/// Retry and rate limit settings for upstream social APIs, read from the environment once
+#[derive(Debug, Clone)]
+pub struct UpstreamPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    /// Longest wait we sit out before retrying; longer `Retry-After`s are returned to the caller
+    pub max_wait: Duration,
+    pub request_timeout: Duration,
+    /// Time allowed for a raw pass-through, including streaming the body
+    pub raw_timeout: Duration,
+    /// Requests per second allowed per provider source
+    pub rate_limits: HashMap<String, f64>,
+}
+
+impl UpstreamPolicy {
+    pub fn from_env() -> Self {
+        let max_retries = std::env::var("SOCIAL_MAX_RETRIES")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(3);
+        let base_delay_ms = std::env::var("SOCIAL_RETRY_BASE_MS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(500);
+        let max_wait_seconds = std::env::var("SOCIAL_RETRY_MAX_WAIT_SECONDS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(30);
+        let timeout_seconds = std::env::var("SOCIAL_REQUEST_TIMEOUT_SECONDS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(30);
+        let raw_timeout_seconds = std::env::var("SOCIAL_RAW_TIMEOUT_SECONDS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(300);
+        // e.g. {"rapidapi_instagram": 5, "tikhub_twitter": 2.5}
+        let rate_limits: HashMap<String, f64> = crate::config::env_json("SOCIAL_RATE_LIMITS").unwrap_or_default();
+
+        Self {
+            max_retries,
+            base_delay: Duration::from_millis(base_delay_ms),
+            max_wait: Duration::from_secs(max_wait_seconds),
+            request_timeout: Duration::from_secs(timeout_seconds),
+            raw_timeout: Duration::from_secs(raw_timeout_seconds),
+            rate_limits: rate_limits.into_iter().filter(|(_, rate)| *rate > 0.0).collect(),
+        }
+    }
+
+    /// Exponential backoff for the given retry (0-based), randomized between half and all of it
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self.base_delay.saturating_mul(2u32.saturating_pow(attempt)).min(self.max_wait);
+        let millis = ceiling.as_millis() as u64;
+        Duration::from_millis(rand::thread_rng().gen_range(millis / 2..=millis.max(1)))
+    }
+}
+
+/// Whether an upstream status is worth retrying
+pub fn is_retryable(status: u16) -> bool {
+    status == 429 || matches!(status, 500 | 502 | 503 | 504)
+}
+
+/// How long the upstream asked us to wait: `Retry-After` (seconds or an HTTP date), or the reset
+/// of an exhausted RapidAPI-style `x-ratelimit-*-remaining` quota
+pub fn requested_wait(headers: &HeaderMap) -> Option<Duration> {
+    retry_after(headers).or_else(|| exhausted_quota_reset(headers))
+}
+
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+    Some((at - Utc::now()).to_std().unwrap_or_default())
+}
+
+/// Reset delay of a rate limit window that has no requests left, e.g.
+/// `x-ratelimit-requests-remaining: 0` with `x-ratelimit-requests-reset: 42`
+pub fn exhausted_quota_reset(headers: &HeaderMap) -> Option<Duration> {
+    headers.iter().find_map(|(name, value)| {
+        let window = name.as_str().strip_prefix("x-ratelimit-")?.strip_suffix("-remaining")?;
+        if value.to_str().ok()?.trim().parse::<i64>().ok()? > 0 {
+            return None;
+        }
+        let reset = headers.get(format!("x-ratelimit-{}-reset", window))?.to_str().ok()?;
+        reset.trim().parse::<u64>().ok().map(Duration::from_secs)
+    })
+}
+
+struct ProviderBucket {
+    tokens: f64,
+    last_refill: Instant,
+    paused_until: Option<Instant>,
+}
+
+/// Client-side request limiter per provider source, so we stay under plan limits
+pub struct UpstreamLimiter {
+    policy: UpstreamPolicy,
+    buckets: Mutex<HashMap<String, ProviderBucket>>,
+}
+
+/// The shared upstream limiter
+pub fn upstream_limiter() -> &'static UpstreamLimiter {
+    static LIMITER: OnceLock<UpstreamLimiter> = OnceLock::new();
+    LIMITER.get_or_init(|| UpstreamLimiter::new(UpstreamPolicy::from_env()))
+}
+
+impl UpstreamLimiter {
+    pub fn new(policy: UpstreamPolicy) -> Self {
+        Self {
+            policy,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn policy(&self) -> &UpstreamPolicy {
+        &self.policy
+    }
+
+    /// Wait until the provider may be called again: after any pause from an exhausted upstream
+    /// quota, and within its configured requests per second (bursts of up to one second's worth).
+    /// A pause longer than `max_wait` is not sat out; its remaining time is returned instead.
+    pub async fn wait_for_slot(&self, source: &str) -> Result<(), Duration> {
+        let rate = self.policy.rate_limits.get(source).copied();
+        loop {
+            let wait = {
+                let mut buckets = self.buckets.lock().unwrap();
+                let now = Instant::now();
+                let bucket = buckets.entry(source.to_string()).or_insert_with(|| ProviderBucket {
+                    tokens: rate.unwrap_or(0.0).max(1.0),
+                    last_refill: now,
+                    paused_until: None,
+                });
+
+                match (bucket.paused_until, rate) {
+                    (Some(until), _) if until > now => {
+                        let remaining = until - now;
+                        if remaining > self.policy.max_wait {
+                            return Err(remaining);
+                        }
+                        Some(remaining)
+                    }
+                    (_, None) => None,
+                    (_, Some(rate)) => {
+                        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+                        bucket.tokens = (bucket.tokens + elapsed * rate).min(rate.max(1.0));
+                        bucket.last_refill = now;
+                        if bucket.tokens >= 1.0 {
+                            bucket.tokens -= 1.0;
+                            None
+                        } else {
+                            Some(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+                        }
+                    }
+                }
+            };
+
+            match wait {
+                None => return Ok(()),
+                Some(duration) => sleep(duration).await,
+            }
+        }
+    }
+
+    /// Hold back all calls to a provider until its upstream quota resets
+    pub fn pause(&self, source: &str, duration: Duration) {
+        let mut buckets = self.buckets.lock().unwrap();
+        let now = Instant::now();
+        let bucket = buckets.entry(source.to_string()).or_insert_with(|| ProviderBucket {
+            tokens: 1.0,
+            last_refill: now,
+            paused_until: None,
+        });
+        let until = now + duration;
+        bucket.paused_until = Some(bucket.paused_until.map_or(until, |current| current.max(until)));
+    }
+}
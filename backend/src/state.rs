@@ -0,0 +1,65 @@
+use axum::extract::FromRef;
+use std::sync::Arc;
+use tokio::time::Duration;
+
+use crate::auth::{ApiKeyStore, JwtVerifier};
+use crate::config::{ConfigError, CrawlLimits, SocialPagingLimits, SocialProxyAllowlist, SocialRawLimits};
+use crate::crawler::CrawlAdmission;
+use crate::db::CassandraState;
+use crate::outbound::HttpClients;
+use crate::social::{init_credential_pools, ProviderRegistry, ResponseCache};
+
+/// Shared application state handed to every route
+#[derive(Clone)]
+pub struct AppState {
+    pub cassandra: CassandraState,
+    pub crawl_limits: Arc<CrawlLimits>,
+    pub crawl_admission: Arc<CrawlAdmission>,
+    pub social_allowlist: Arc<SocialProxyAllowlist>,
+    pub api_keys: Arc<ApiKeyStore>,
+    pub jwt: Arc<JwtVerifier>,
+    pub social_providers: Arc<ProviderRegistry>,
+    pub social_paging: Arc<SocialPagingLimits>,
+    pub social_raw: Arc<SocialRawLimits>,
+    pub social_cache: Arc<ResponseCache>,
+    pub http: Arc<HttpClients>,
+}
+
+impl AppState {
+    /// Build the state from the environment. Fails on invalid security-relevant configuration
+    /// (the social proxy allowlist, upstream credentials and the outbound HTTP proxy).
+    pub fn new(cassandra: CassandraState) -> Result<Self, ConfigError> {
+        let social_allowlist = SocialProxyAllowlist::from_env()?;
+        init_credential_pools()?;
+
+        let crawl_limits = CrawlLimits::from_env();
+        let crawl_admission = CrawlAdmission::new(
+            crawl_limits.max_concurrent_crawls,
+            crawl_limits.max_queued_crawls,
+            Duration::from_secs(crawl_limits.retry_after_seconds),
+        );
+
+        let social_cache = ResponseCache::from_env(&cassandra);
+        let http = HttpClients::from_env()?;
+
+        Ok(Self {
+            cassandra,
+            crawl_limits: Arc::new(crawl_limits),
+            crawl_admission: Arc::new(crawl_admission),
+            social_allowlist: Arc::new(social_allowlist),
+            api_keys: Arc::new(ApiKeyStore::from_env()),
+            jwt: Arc::new(JwtVerifier::from_env()),
+            social_providers: Arc::new(ProviderRegistry::from_env()),
+            social_paging: Arc::new(SocialPagingLimits::from_env()),
+            social_raw: Arc::new(SocialRawLimits::from_env()),
+            social_cache: Arc::new(social_cache),
+            http: Arc::new(http),
+        })
+    }
+}
+
+impl FromRef<AppState> for CassandraState {
+    fn from_ref(state: &AppState) -> Self {
+        state.cassandra.clone()
+    }
+}
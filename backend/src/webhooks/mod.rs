@@ -0,0 +1,374 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+use std::sync::Arc;
+use tokio::task::JoinSet;
+use tokio::time::{sleep, Duration};
+use url::Url;
+use uuid::Uuid;
+
+use crate::crawler::KeywordMatch;
+use crate::db::{claim_webhook_alert, finish_webhook_alert, insert_webhook_delivery, list_webhooks, CassandraState};
+use crate::outbound::HttpClients;
+
+// Delivery attempts per alert before giving up
+const MAX_DELIVERY_ATTEMPTS: u32 = 5;
+
+// Delay before the first retry; doubled on every further attempt
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+// Timeout for a single delivery request
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookSubscription {
+    pub id: Uuid,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub keywords: Vec<String>,
+    pub domains: Vec<String>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebhookRequest {
+    pub url: String,
+    pub secret: String,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub domains: Vec<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub source_url: String,
+    pub keyword: String,
+    pub attempts: u32,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub delivered_at: DateTime<Utc>,
+}
+
+impl WebhookSubscription {
+    /// Whether a keyword match passes this subscription's keyword and domain filters.
+    /// Empty filters match everything.
+    pub fn matches(&self, keyword_match: &KeywordMatch) -> bool {
+        let keyword_ok = self.keywords.is_empty()
+            || self
+                .keywords
+                .iter()
+                .any(|keyword| keyword.eq_ignore_ascii_case(&keyword_match.keyword));
+
+        let host = Url::parse(&keyword_match.source_url)
+            .ok()
+            .and_then(|url| url.host_str().map(|h| h.to_lowercase()));
+        let domain_ok = self.domains.is_empty()
+            || host
+                .map(|host| {
+                    self.domains.iter().any(|domain| {
+                        let domain = domain.trim_start_matches("www.").to_lowercase();
+                        host.trim_start_matches("www.") == domain || host.ends_with(&format!(".{}", domain))
+                    })
+                })
+                .unwrap_or(false);
+
+        keyword_ok && domain_ok
+    }
+}
+
+/// HMAC-SHA256 signature of a payload, sent as `X-Webhook-Signature: sha256=<hex>`
+pub fn sign_payload(secret: &str, payload: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(payload);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Notify webhook subscribers about new keyword matches from a stored crawl result.
+/// Each subscription is alerted at most once per page/keyword pair; alerts whose delivery
+/// failed are retried by the next crawl that matches them. Subscriptions are served concurrently.
+/// A subscription whose URL no longer passes the outbound policy is skipped.
+pub async fn dispatch_matches(state: CassandraState, http: Arc<HttpClients>, crawl_result_id: Uuid, matches: Vec<KeywordMatch>) {
+    if matches.is_empty() {
+        return;
+    }
+
+    let subscriptions = match list_webhooks(&state.session, &state.keyspace).await {
+        Ok(subscriptions) => subscriptions,
+        Err(e) => {
+            eprintln!("[Webhooks] Failed to load subscriptions: {}", e);
+            return;
+        }
+    };
+
+    let mut deliveries = JoinSet::new();
+    for subscription in subscriptions.into_iter().filter(|s| s.enabled) {
+        let subscription_matches: Vec<KeywordMatch> = matches.iter().filter(|m| subscription.matches(m)).cloned().collect();
+        if subscription_matches.is_empty() {
+            continue;
+        }
+        // Checked again at delivery time: DNS may have moved, and behind a proxy nothing else checks it
+        let allowed = match Url::parse(&subscription.url) {
+            Ok(url) => http.check_target(&url).await.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = allowed {
+            eprintln!("[Webhooks] Skipping subscription {}: {}", subscription.id, e);
+            continue;
+        }
+        deliveries.spawn(dispatch_to_subscription(
+            state.clone(),
+            http.shared(),
+            subscription,
+            crawl_result_id,
+            subscription_matches,
+        ));
+    }
+    while deliveries.join_next().await.is_some() {}
+}
+
+// Deliver one subscription's alerts in order, recording each outcome for later retries
+async fn dispatch_to_subscription(
+    state: CassandraState,
+    client: Client,
+    subscription: WebhookSubscription,
+    crawl_result_id: Uuid,
+    matches: Vec<KeywordMatch>,
+) {
+    for keyword_match in &matches {
+        // Several contexts of the same keyword on one page share a single alert
+        match claim_webhook_alert(&state.session, &state.keyspace, subscription.id, &keyword_match.source_url, &keyword_match.keyword).await {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(e) => {
+                eprintln!("[Webhooks] Failed to record alert for {}: {}", subscription.id, e);
+                continue;
+            }
+        }
+
+        let delivery = deliver(&client, &subscription, crawl_result_id, keyword_match).await;
+        let delivered = delivery.error.is_none();
+        if let Err(e) = finish_webhook_alert(
+            &state.session,
+            &state.keyspace,
+            subscription.id,
+            &keyword_match.source_url,
+            &keyword_match.keyword,
+            delivered,
+        )
+        .await
+        {
+            eprintln!("[Webhooks] Failed to record alert outcome for {}: {}", subscription.id, e);
+        }
+        if let Err(e) = insert_webhook_delivery(&state.session, &state.keyspace, &delivery).await {
+            eprintln!("[Cassandra] Webhook delivery log insert failed: {}", e);
+        }
+    }
+}
+
+// Deliver one alert with exponential backoff, returning the delivery log entry
+async fn deliver(client: &Client, subscription: &WebhookSubscription, crawl_result_id: Uuid, keyword_match: &KeywordMatch) -> WebhookDelivery {
+    let delivery_id = Uuid::new_v4();
+    let payload = json!({
+        "event": "keyword_match",
+        "delivery_id": delivery_id,
+        "subscription_id": subscription.id,
+        "crawl_result_id": crawl_result_id,
+        "source_url": keyword_match.source_url,
+        "match": keyword_match,
+    });
+    let body = serde_json::to_vec(&payload).unwrap_or_default();
+    let signature = sign_payload(&subscription.secret, &body);
+
+    let mut attempts = 0;
+    let mut delay = INITIAL_RETRY_DELAY;
+    let mut status_code = None;
+    let mut error = None;
+
+    while attempts < MAX_DELIVERY_ATTEMPTS {
+        attempts += 1;
+        let result = client
+            .post(&subscription.url)
+            .header("content-type", "application/json")
+            .header("x-webhook-id", delivery_id.to_string())
+            .header("x-webhook-signature", &signature)
+            .timeout(DELIVERY_TIMEOUT)
+            .body(body.clone())
+            .send()
+            .await;
+
+        match result {
+            Ok(resp) if resp.status().is_success() => {
+                status_code = Some(resp.status().as_u16());
+                error = None;
+                break;
+            }
+            Ok(resp) => {
+                status_code = Some(resp.status().as_u16());
+                error = Some(format!("Subscriber responded with {}", resp.status()));
+            }
+            Err(e) => {
+                status_code = None;
+                error = Some(format!("Request failed: {}", e));
+            }
+        }
+
+        if attempts < MAX_DELIVERY_ATTEMPTS {
+            sleep(delay).await;
+            delay *= 2;
+        }
+    }
+
+    WebhookDelivery {
+        id: delivery_id,
+        subscription_id: subscription.id,
+        source_url: keyword_match.source_url.clone(),
+        keyword: keyword_match.keyword.clone(),
+        attempts,
+        status_code,
+        error,
+        delivered_at: Utc::now(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Bytes;
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    // What the stand-in subscriber saw and the statuses it answers with, in order
+    #[derive(Clone, Default)]
+    struct Subscriber {
+        received: Arc<Mutex<Vec<(HeaderMap, Bytes)>>>,
+        statuses: Arc<Mutex<VecDeque<u16>>>,
+    }
+
+    async fn receive(State(subscriber): State<Subscriber>, headers: HeaderMap, body: Bytes) -> StatusCode {
+        subscriber.received.lock().unwrap().push((headers, body));
+        let status = subscriber.statuses.lock().unwrap().pop_front().unwrap_or(200);
+        StatusCode::from_u16(status).unwrap()
+    }
+
+    // Helper function to run a local webhook receiver answering with `statuses`, then 200
+    async fn serve_subscriber(statuses: &[u16]) -> (String, Subscriber) {
+        let subscriber = Subscriber::default();
+        subscriber.statuses.lock().unwrap().extend(statuses);
+        let app = Router::new().route("/hook", post(receive)).with_state(subscriber.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (format!("http://{}/hook", addr), subscriber)
+    }
+
+    fn subscription(url: &str, keywords: &[&str], domains: &[&str]) -> WebhookSubscription {
+        WebhookSubscription {
+            id: Uuid::new_v4(),
+            url: url.to_string(),
+            secret: "test-secret".to_string(),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            domains: domains.iter().map(|d| d.to_string()).collect(),
+            enabled: true,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn keyword_match(keyword: &str, source_url: &str) -> KeywordMatch {
+        KeywordMatch {
+            keyword: keyword.to_string(),
+            context: format!("some text about {}", keyword),
+            cleaned_text: format!("some text about {}", keyword),
+            count: 1,
+            relevance_score: Some(1.0),
+            source_url: source_url.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn deliver_posts_a_signed_payload() {
+        let (url, subscriber) = serve_subscriber(&[]).await;
+        let subscription = subscription(&url, &[], &[]);
+        let crawl_result_id = Uuid::new_v4();
+
+        let delivery = deliver(&Client::new(), &subscription, crawl_result_id, &keyword_match("rust", "https://news.example.com/a")).await;
+
+        assert_eq!(delivery.attempts, 1);
+        assert_eq!(delivery.status_code, Some(200));
+        assert!(delivery.error.is_none());
+
+        let received = subscriber.received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let (headers, body) = &received[0];
+        assert_eq!(headers["x-webhook-signature"], sign_payload("test-secret", body).as_str());
+        assert_eq!(headers["x-webhook-id"], delivery.id.to_string().as_str());
+
+        let payload: serde_json::Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(payload["event"], "keyword_match");
+        assert_eq!(payload["crawl_result_id"], crawl_result_id.to_string());
+        assert_eq!(payload["match"]["keyword"], "rust");
+    }
+
+    #[tokio::test]
+    async fn deliver_retries_until_the_subscriber_accepts() {
+        let (url, subscriber) = serve_subscriber(&[500, 503]).await;
+        let subscription = subscription(&url, &[], &[]);
+
+        let delivery = deliver(&Client::new(), &subscription, Uuid::new_v4(), &keyword_match("rust", "https://example.com/")).await;
+
+        assert_eq!(delivery.attempts, 3);
+        assert_eq!(delivery.status_code, Some(200));
+        assert!(delivery.error.is_none());
+
+        // Retries resend the same delivery so subscribers can dedupe on the id
+        let received = subscriber.received.lock().unwrap();
+        assert_eq!(received.len(), 3);
+        assert!(received.iter().all(|(headers, _)| headers["x-webhook-id"] == received[0].0["x-webhook-id"]));
+    }
+
+    #[tokio::test]
+    async fn deliver_reports_a_rejected_alert_as_failed() {
+        let (url, _subscriber) = serve_subscriber(&[500; MAX_DELIVERY_ATTEMPTS as usize]).await;
+        let subscription = subscription(&url, &[], &[]);
+
+        // Unreachable subscribers fail the same way, without a status code
+        let unreachable = WebhookSubscription {
+            url: "http://127.0.0.1:9/hook".to_string(),
+            ..subscription.clone()
+        };
+        let (rejected, unreachable) = tokio::join!(
+            deliver(&Client::new(), &subscription, Uuid::new_v4(), &keyword_match("rust", "https://example.com/")),
+            deliver(&Client::new(), &unreachable, Uuid::new_v4(), &keyword_match("rust", "https://example.com/")),
+        );
+
+        assert_eq!(rejected.attempts, MAX_DELIVERY_ATTEMPTS);
+        assert_eq!(rejected.status_code, Some(500));
+        assert!(rejected.error.is_some());
+        assert_eq!(unreachable.status_code, None);
+        assert!(unreachable.error.is_some());
+    }
+
+    #[test]
+    fn subscription_filters_by_keyword_and_domain() {
+        let subscription = subscription("http://127.0.0.1/hook", &["Rust"], &["www.example.com"]);
+
+        assert!(subscription.matches(&keyword_match("rust", "https://example.com/a")));
+        assert!(subscription.matches(&keyword_match("rust", "https://blog.example.com/a")));
+        assert!(!subscription.matches(&keyword_match("go", "https://example.com/a")));
+        assert!(!subscription.matches(&keyword_match("rust", "https://notexample.com/a")));
+    }
+}
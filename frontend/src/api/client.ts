@@ -3,13 +3,38 @@ import axios from 'axios';
 // Define the API base URL
 const API_URL = import.meta.env.VITE_API_URL || 'http://localhost:8081';
 
+// Bearer credential sent on every request: a logged-in user's JWT, or a configured API key
+export const setAuthToken = (token: string | null) => {
+  if (token) {
+    axios.defaults.headers.common['Authorization'] = `Bearer ${token}`;
+  } else {
+    delete axios.defaults.headers.common['Authorization'];
+  }
+};
+
+const API_KEY = import.meta.env.VITE_API_KEY;
+if (API_KEY) {
+  setAuthToken(API_KEY);
+}
+
 // Define types based on backend API - matching the curl example structure
 export interface CrawlRequest {
   url: string;
   keywords: string[];
   max_pages?: number;
+  follow_pagination?: boolean;
+  max_pagination_pages?: number;
   date_from?: string | null; // ISO 8601 date string (YYYY-MM-DD) or null
   date_to?: string | null;   // ISO 8601 date string (YYYY-MM-DD) or null
+  languages?: string[];      // ISO 639-1 codes, e.g. ["en", "id"]
+  similarity_threshold?: number; // Near-duplicate collapse threshold (0.0 - 1.0)
+  tracking_params?: string[]; // Query params stripped during URL canonicalization, e.g. ["utm_*"]
+  include_url_patterns?: string[]; // e.g. ["/news/", "/blog/"]
+  exclude_url_patterns?: string[]; // e.g. ["/tag/", "/login"]
+  render_js?: boolean; // Render with headless Chrome (backend `chrome` feature)
+  wait_for_selector?: string;
+  wait_for_idle_ms?: number;
+  only_changed?: boolean; // Only return pages new or changed since the previous crawl
 }
 
 export interface KeywordMatch {
@@ -27,6 +52,22 @@ export interface CrawlMetadata {
   content_summary?: string;
   last_modified?: string; // ISO 8601 date string for page last modified date
   published_date?: string; // ISO 8601 date string for page published date
+  duplicates_collapsed?: number;
+  pagination_pages_followed?: number;
+  unchanged_pages?: number;
+}
+
+export interface PageResult {
+  url: string;
+  title?: string;
+  language?: string; // Detected ISO 639-1 language code
+  match_count: number;
+  duplicates?: string[]; // URLs collapsed into this page as near-duplicates
+}
+
+export interface ExcludedLinkCount {
+  rule: string;
+  count: number;
 }
 
 export interface DomainResult {
@@ -35,6 +76,9 @@ export interface DomainResult {
   matches: KeywordMatch[];
   pages_crawled: number;
   has_more_pages: boolean;
+  truncated?: boolean; // Hit the crawl deadline; only pages fetched before it are included
+  pages?: PageResult[];
+  excluded_links?: ExcludedLinkCount[];
   metadata?: CrawlMetadata;
   error?: string;
 }
@@ -44,6 +88,55 @@ export interface CrawlResult {
   total_pages_crawled: number;
   total_processing_time_ms: number;
   crawl_timestamp: string;
+  truncated?: boolean;
+}
+
+export interface PostMetrics {
+  likes?: number | null;
+  comments?: number | null;
+  shares?: number | null;
+  views?: number | null;
+}
+
+export interface SocialPost {
+  platform: 'twitter' | 'tiktok' | 'instagram';
+  post_id: string;
+  author?: string | null;
+  text?: string | null;
+  created_at?: string | null;
+  metrics: PostMetrics;
+  media_urls: string[];
+  permalink?: string | null;
+}
+
+export interface SocialPageOptions {
+  cursor?: string;
+  max_items?: number;
+  max_pages?: number;
+}
+
+export interface SocialProxyResponse {
+  status: number;
+  // One upstream page, or every page fetched when paginating
+  data?: unknown;
+  data_text?: string;
+  normalized?: SocialPost[];
+  pages?: number;
+  next_cursor?: string | null;
+}
+
+export interface SocialSearchRequest {
+  keyword: string;
+  platforms?: Array<'twitter' | 'tiktok' | 'instagram'>;
+  since?: string;
+  until?: string;
+  max_items?: number;
+}
+
+export interface SocialSearchResponse {
+  keyword: string;
+  posts: SocialPost[];
+  platforms: Record<string, { status?: number; count?: number; next_cursor?: string | null; error?: string }>;
 }
 
 // API client for crawler
@@ -63,30 +156,39 @@ export const crawlerApi = {
 
 // API client for social media proxies
 export const socialApi = {
-  tikhubTwitter: async (path: string, params?: Record<string, unknown>, method: 'GET' | 'POST' = 'GET') => {
-    const response = await axios.post(`${API_URL}/social/tikhub/twitter`, { path, params, method });
+  tikhubTwitter: async (path: string, params?: Record<string, unknown>, method: 'GET' | 'POST' = 'GET', paging: SocialPageOptions = {}): Promise<SocialProxyResponse> => {
+    const response = await axios.post(`${API_URL}/social/tikhub/twitter`, { path, params, method, ...paging });
     return response.data;
   },
-  tikhubTiktok: async (path: string, params?: Record<string, unknown>, method: 'GET' | 'POST' = 'GET') => {
-    const response = await axios.post(`${API_URL}/social/tikhub/tiktok`, { path, params, method });
+  tikhubTiktok: async (path: string, params?: Record<string, unknown>, method: 'GET' | 'POST' = 'GET', paging: SocialPageOptions = {}): Promise<SocialProxyResponse> => {
+    const response = await axios.post(`${API_URL}/social/tikhub/tiktok`, { path, params, method, ...paging });
     return response.data;
   },
-  tikhubGeneric: async (service: string, path: string, params?: Record<string, unknown>, method: 'GET' | 'POST' = 'GET') => {
+  tikhubGeneric: async (service: string, path: string, params?: Record<string, unknown>, method: 'GET' | 'POST' = 'GET'): Promise<SocialProxyResponse> => {
     const response = await axios.post(`${API_URL}/social/tikhub/generic`, { service, path, params, method });
     return response.data;
   },
-  rapidInstagram: async (path: string, params?: Record<string, unknown>, method: 'GET' | 'POST' = 'GET') => {
-    const response = await axios.post(`${API_URL}/social/rapidapi/instagram`, { path, params, method });
+  rapidInstagram: async (path: string, params?: Record<string, unknown>, method: 'GET' | 'POST' = 'GET', paging: SocialPageOptions = {}): Promise<SocialProxyResponse> => {
+    const response = await axios.post(`${API_URL}/social/rapidapi/instagram`, { path, params, method, ...paging });
     return response.data;
   },
-  rapidTwitterV24: async (path: string, params?: Record<string, unknown>, method: 'GET' | 'POST' = 'GET') => {
-    const response = await axios.post(`${API_URL}/social/rapidapi/twitter-v24`, { path, params, method });
+  rapidTwitterV24: async (path: string, params?: Record<string, unknown>, method: 'GET' | 'POST' = 'GET', paging: SocialPageOptions = {}): Promise<SocialProxyResponse> => {
+    const response = await axios.post(`${API_URL}/social/rapidapi/twitter-v24`, { path, params, method, ...paging });
     return response.data;
   },
-  rapidGeneric: async (host: string, path: string, params?: Record<string, unknown>, method: 'GET' | 'POST' = 'GET') => {
+  search: async (request: SocialSearchRequest): Promise<SocialSearchResponse> => {
+    const response = await axios.post(`${API_URL}/social/search`, request);
+    return response.data;
+  },
+  rapidGeneric: async (host: string, path: string, params?: Record<string, unknown>, method: 'GET' | 'POST' = 'GET'): Promise<SocialProxyResponse> => {
     const response = await axios.post(`${API_URL}/social/rapidapi/generic`, { host, path, params, method });
     return response.data;
   },
+  // Upstream body as-is (images, video downloads, large JSON) from a registered provider
+  raw: async (provider: string, path: string, params?: Record<string, unknown>, method: 'GET' | 'POST' = 'GET'): Promise<Blob> => {
+    const response = await axios.post(`${API_URL}/social/providers/${provider}`, { path, params, method, raw: true }, { responseType: 'blob' });
+    return response.data;
+  },
 };
 
 // API client for health check